@@ -39,6 +39,7 @@
             cairo
             pango
             xorg.libX11
+            xorg.libXext
             xorg.libXi
             xorg.libXrandr
             xorg.libXcursor
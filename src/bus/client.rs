@@ -0,0 +1,91 @@
+use std::time::Duration;
+
+use dbus::{
+    arg::{PropMap, RefArg, Variant},
+    blocking::Connection,
+};
+
+use crate::bus::dbus::{Notification, Timeout, Urgency, PATH};
+
+const NOTIFICATIONS_NAME: &str = "org.freedesktop.Notifications";
+const NOTIFICATIONS_INTERFACE: &str = "org.freedesktop.Notifications";
+const CALL_TIMEOUT: Duration = Duration::from_secs(5);
+
+// Sends notifications over the session bus, like any other application would.
+// Used by `--send` and `--replay`, so layouts can be tried out without any other tools.
+pub struct NotifyClient {
+    conn: Connection,
+}
+
+impl NotifyClient {
+    pub fn new() -> Result<Self, dbus::Error> {
+        Ok(Self {
+            conn: Connection::new_session()?,
+        })
+    }
+
+    // Send `notification` through `Notify`, returning the ID the daemon gave it.
+    // `image_path` is sent as the `image-path` hint, so the daemon loads it just like it would for
+    // anyone else; it should be absolute, since the daemon won't be in the same directory.
+    pub fn send(&self, notification: &Notification, image_path: Option<&str>) -> Result<u32, dbus::Error> {
+        let mut actions = Vec::new();
+        // `HashMap` has no order, but we want the default action first, like most senders do.
+        if let Some((key, label)) = notification.get_default_action() {
+            actions.push(key);
+            actions.push(label);
+        }
+        for (key, label) in notification.actions.iter().filter(|(k, _)| *k != "default") {
+            actions.push(key.to_owned());
+            actions.push(label.to_owned());
+        }
+
+        let mut hints = PropMap::new();
+        let urgency: u8 = match notification.urgency {
+            Urgency::Low => 0,
+            Urgency::Normal => 1,
+            Urgency::Critical => 2,
+        };
+        hints.insert("urgency".to_owned(), variant(urgency));
+        if let Some(p) = notification.percentage {
+            hints.insert("value".to_owned(), variant((p * 100.0).round() as i32));
+        }
+        if let Some(tag) = &notification.tag {
+            hints.insert("wired-tag".to_owned(), variant(tag.to_owned()));
+        }
+        if let Some(note) = &notification.note {
+            hints.insert("wired-note".to_owned(), variant(note.to_owned()));
+        }
+        if let Some(path) = image_path {
+            hints.insert("image-path".to_owned(), variant(path.to_owned()));
+        }
+
+        let expire_timeout = match notification.timeout {
+            Timeout::Milliseconds(ms) => ms,
+            Timeout::NeverExpire => 0,
+            Timeout::Default => -1,
+        };
+
+        let proxy = self.conn.with_proxy(NOTIFICATIONS_NAME, PATH, CALL_TIMEOUT);
+        let (id,): (u32,) = proxy.method_call(
+            NOTIFICATIONS_INTERFACE,
+            "Notify",
+            (
+                notification.app_name.as_str(),
+                // Never replace anything; IDs from a recording won't mean anything now.
+                0u32,
+                "",
+                notification.summary.as_str(),
+                notification.body.as_str(),
+                actions,
+                hints,
+                expire_timeout,
+            ),
+        )?;
+
+        Ok(id)
+    }
+}
+
+fn variant<T: RefArg + 'static>(value: T) -> Variant<Box<dyn RefArg>> {
+    Variant(Box::new(value))
+}
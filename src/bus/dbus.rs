@@ -1,7 +1,7 @@
 #![allow(static_mut_refs)]
 
 use std::collections::{HashMap, VecDeque};
-use std::sync::atomic::AtomicU32;
+use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
 use std::sync::mpsc::{self, Receiver, Sender};
 use std::thread::{self, JoinHandle};
 use std::time::Duration;
@@ -9,23 +9,30 @@ use std::time::Duration;
 use dbus::{
     self,
     arg::{self, PropMap, RefArg},
-    blocking::{stdintf::org_freedesktop_dbus::RequestNameReply, Connection},
-    channel::MatchingReceiver,
-    message::MatchRule,
+    blocking::{
+        stdintf::org_freedesktop_dbus::{PropertiesPropertiesChanged, RequestNameReply},
+        Connection,
+    },
+    channel::{MatchingReceiver, Sender as _},
+    message::{MatchRule, SignalArgs},
+    strings::Path,
     MessageType,
 };
 use dbus_crossroads::Crossroads;
 use image::{self, DynamicImage, ImageBuffer};
 
 use chrono::{offset::Local, DateTime};
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 
 use tiny_skia;
 
-use crate::bus::dbus_codegen::{self, OrgFreedesktopNotifications};
+use crate::bus::dbus_codegen::{
+    self, IoGithubToqozzWired, IoGithubToqozzWiredDoNotDisturbChanged, OrgFreedesktopNotifications,
+};
 use crate::config::ZeroTimeoutBehavior;
 use crate::icons;
 use crate::maths_utility;
+use crate::rendering::text;
 use crate::Config;
 
 static ID_COUNT: AtomicU32 = AtomicU32::new(1);
@@ -33,7 +40,15 @@ pub fn fetch_id() -> u32 {
     ID_COUNT.fetch_add(1, std::sync::atomic::Ordering::Relaxed)
 }
 
+// Mirror of the manager's do not disturb state, so the `DoNotDisturb` property can be read from the
+// dbus thread.  Only the main thread writes to this, via `publish_dnd`.
+static DND: AtomicBool = AtomicBool::new(false);
+
 pub const PATH: &str = "/org/freedesktop/Notifications";
+pub const WIRED_INTERFACE: &str = "io.github.toqozz.Wired";
+// How long we wait for the main loop to answer a request before giving up.  The main loop only
+// checks messages every `poll_interval` (or `idle_poll_interval`), so this needs to be generous.
+const REPLY_TIMEOUT: Duration = Duration::from_secs(5);
 // Global access to dbus connection is necessary to avoid spaghetti.
 static mut DBUS_CONN: Option<Connection> = None;
 
@@ -127,6 +142,63 @@ impl OrgFreedesktopNotifications for Notify {
     }
 }
 
+// Our own control interface, for things that would otherwise need the wired socket.
+// Anything that touches windows or history has to happen on the main thread, so we just forward
+// messages there.
+impl IoGithubToqozzWired for Notify {
+    fn drop_all(&mut self) -> Result<(), dbus::MethodErr> {
+        match self.sender.send(Message::DropAll) {
+            Ok(_) => Ok(()),
+            Err(e) => Err(dbus::MethodErr::failed(&e)),
+        }
+    }
+
+    fn show_history(&mut self, count: u32) -> Result<(), dbus::MethodErr> {
+        match self.sender.send(Message::ShowHistory(count)) {
+            Ok(_) => Ok(()),
+            Err(e) => Err(dbus::MethodErr::failed(&e)),
+        }
+    }
+
+    fn list_notifications(&mut self) -> Result<Vec<(u32, String, String, String)>, dbus::MethodErr> {
+        let (sender, receiver) = mpsc::channel();
+        if let Err(e) = self.sender.send(Message::ListNotifications(sender)) {
+            return Err(dbus::MethodErr::failed(&e));
+        }
+
+        match receiver.recv_timeout(REPLY_TIMEOUT) {
+            Ok(list) => Ok(list),
+            Err(e) => Err(dbus::MethodErr::failed(&e)),
+        }
+    }
+
+    fn reload(&mut self) -> Result<(), dbus::MethodErr> {
+        let (sender, receiver) = mpsc::channel();
+        if let Err(e) = self.sender.send(Message::Reload(sender)) {
+            return Err(dbus::MethodErr::failed(&e));
+        }
+
+        // Pass validation errors back to the caller, so they know what to fix.
+        match receiver.recv_timeout(REPLY_TIMEOUT) {
+            Ok(Ok(())) => Ok(()),
+            Ok(Err(problem)) => Err(dbus::MethodErr::failed(&problem)),
+            Err(e) => Err(dbus::MethodErr::failed(&e)),
+        }
+    }
+
+    fn do_not_disturb(&self) -> Result<bool, dbus::MethodErr> {
+        Ok(DND.load(Ordering::Relaxed))
+    }
+
+    fn set_do_not_disturb(&self, value: bool) -> Result<(), dbus::MethodErr> {
+        // The change signals are sent by the main thread once the state has actually changed.
+        match self.sender.send(Message::SetDnd(value)) {
+            Ok(_) => Ok(()),
+            Err(e) => Err(dbus::MethodErr::failed(&e)),
+        }
+    }
+}
+
 pub fn init_dbus_thread() -> (JoinHandle<()>, Receiver<Message>) {
     let (sender, receiver) = mpsc::channel();
 
@@ -174,7 +246,8 @@ pub fn init_dbus_thread() -> (JoinHandle<()>, Receiver<Message>) {
 
     let mut cr = Crossroads::new();
     let token = dbus_codegen::register_org_freedesktop_notifications::<Notify>(&mut cr);
-    cr.insert(PATH, &[token], Notify { sender });
+    let wired_token = dbus_codegen::register_io_github_toqozz_wired::<Notify>(&mut cr);
+    cr.insert(PATH, &[token, wired_token], Notify { sender });
 
     c.start_receive(
         dbus::message::MatchRule::new_method_call(),
@@ -211,7 +284,29 @@ pub fn get_connection() -> &'static Connection {
     }
 }
 
-#[derive(Debug, Clone, Serialize)]
+// Update the `DoNotDisturb` property and let anyone listening know that it changed.
+// We send both our own signal and the standard `PropertiesChanged`, since most desktop tools only
+// know about the latter.
+pub fn publish_dnd(enabled: bool) {
+    DND.store(enabled, Ordering::Relaxed);
+
+    let path = Path::new(PATH).expect("Failed to create DBus path.");
+    let conn = get_connection();
+
+    let message = IoGithubToqozzWiredDoNotDisturbChanged { enabled };
+    let _result = conn.send(message.to_emit_message(&path));
+
+    let mut changed_properties = PropMap::new();
+    changed_properties.insert("DoNotDisturb".to_owned(), arg::Variant(Box::new(enabled)));
+    let message = PropertiesPropertiesChanged {
+        interface_name: WIRED_INTERFACE.to_owned(),
+        changed_properties,
+        invalidated_properties: vec![],
+    };
+    let _result = conn.send(message.to_emit_message(&path));
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum Urgency {
     Low,
     Normal,
@@ -228,6 +323,15 @@ impl Default for Urgency {
 pub enum Message {
     Close(u32),
     Notify(Notification),
+
+    // Control messages, from our own dbus interface.
+    DropAll,
+    ShowHistory(u32),
+    SetDnd(bool),
+    // Replies with the reason the config couldn't be reloaded, if any.
+    Reload(Sender<Result<(), String>>),
+    // (id, app_name, summary, body) of each notification on screen, oldest first.
+    ListNotifications(Sender<Vec<(u32, String, String, String)>>),
 }
 
 #[allow(clippy::upper_case_acronyms)]
@@ -237,37 +341,57 @@ pub enum ImageData {
     Dynamic(DynamicImage),
 }
 
-#[derive(Clone, Serialize, Debug)]
+#[derive(Clone, Serialize, Deserialize, Debug, Default)]
 pub enum Timeout {
     Milliseconds(i32),
     NeverExpire,
+    // The sender didn't ask for anything in particular, so the config decides.
+    // Resolved when the notification gets a window (see `NotifyWindow::new`).
+    #[default]
+    Default,
 }
 
-#[derive(Clone, Serialize)]
+// Notifications can also be read back from the JSON that `print_to_file` writes (see `from_json`),
+// in which case anything left out gets a default.
+#[derive(Clone, Serialize, Deserialize)]
 pub struct Notification {
+    #[serde(default)]
     pub id: u32,
+    #[serde(default)]
     pub tag: Option<String>,
+    #[serde(default)]
     pub note: Option<String>,
 
+    #[serde(default)]
     pub app_name: String,
 
+    #[serde(default)]
     pub summary: String,
+    #[serde(default)]
     pub body: String,
+    #[serde(default)]
     pub actions: HashMap<String, String>,
     #[serde(skip)]
     pub app_image: Option<ImageData>,
     #[serde(skip)]
     pub hint_image: Option<ImageData>,
+    #[serde(default)]
     pub percentage: Option<f32>,
 
+    #[serde(default)]
     pub urgency: Urgency,
 
-    #[serde(serialize_with = "serialize_datetime")]
+    #[serde(
+        serialize_with = "serialize_datetime",
+        deserialize_with = "deserialize_datetime",
+        default = "Local::now"
+    )]
     pub time: DateTime<Local>,
+    #[serde(default)]
     pub timeout: Timeout,
 }
 
-use serde::Serializer;
+use serde::{Deserializer, Serializer};
 
 fn serialize_datetime<S>(datetime: &DateTime<Local>, serializer: S) -> Result<S::Ok, S::Error>
 where
@@ -276,6 +400,16 @@ where
     serializer.serialize_i64(datetime.timestamp())
 }
 
+fn deserialize_datetime<'de, D>(deserializer: D) -> Result<DateTime<Local>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let timestamp = i64::deserialize(deserializer)?;
+    DateTime::from_timestamp(timestamp, 0)
+        .map(|t| t.with_timezone(&Local))
+        .ok_or_else(|| serde::de::Error::custom(format!("invalid timestamp: {}", timestamp)))
+}
+
 impl std::fmt::Debug for Notification {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
         write!(
@@ -286,7 +420,61 @@ impl std::fmt::Debug for Notification {
     }
 }
 
+fn image_from_path(path: &str) -> Option<ImageData> {
+    //let start = std::time::Instant::now();
+    //dbg!("Loading image from path...");
+
+    let parts: Vec<&str> = path.split('.').collect();
+    let maybe_image = match parts.last() {
+        // tiny_skia can load pngs and svgs, so we use that where we can, otherwise we
+        // try to load with the image-rs crate.  Hopefully we can move away from that
+        // eventually.
+        Some(ext) => match ext.to_lowercase().as_str() {
+            "svg" => {
+                let svg_data = std::fs::read(path).ok()?;
+                Some(ImageData::SVG(svg_data))
+            }
+            "png" => tiny_skia::Pixmap::load_png(path)
+                .ok()
+                .map(|p| image::RgbaImage::from_raw(p.width(), p.height(), p.take()))
+                .map(|i| ImageData::Dynamic(DynamicImage::ImageRgba8(i.unwrap()))),
+            _ => None,
+        },
+        None => return None,
+    };
+
+    //let end = std::time::Instant::now();
+    //dbg!(end - start);
+
+    // Fall back to trying to open with image-rs.
+    maybe_image.or_else(|| image::open(path).ok().map(ImageData::Dynamic))
+}
+
+// A notification as JSON, like one line of `print_to_file`'s output.
+// Images can't be stored that way, so an `image` path can be given instead.
+#[derive(Deserialize)]
+pub struct NotificationJson {
+    #[serde(flatten)]
+    pub notification: Notification,
+    pub image: Option<String>,
+}
+
 impl Notification {
+    // Read a notification from JSON (see `NotificationJson`), loading its image if it has one.
+    pub fn from_json(json: &str) -> Result<Self, String> {
+        let parsed: NotificationJson = serde_json::from_str(json).map_err(|e| e.to_string())?;
+        let mut notification = parsed.notification;
+        if let Some(path) = parsed.image {
+            notification.hint_image =
+                Some(image_from_path(&path).ok_or_else(|| format!("Couldn't load image: {}", path))?);
+        }
+        if notification.id == 0 {
+            notification.id = fetch_id();
+        }
+
+        Ok(notification)
+    }
+
     pub fn from_self(summary: &str, body: &str, timeout: Timeout) -> Self {
         let id = fetch_id();
         Self {
@@ -326,12 +514,19 @@ impl Notification {
 
         // Pango is a bitch about ampersands, and also doesn't decode html entities for us, which
         // applications /love/ to send -- we need to escape ampersands and decode html entities.
-        let mut summary = maths_utility::escape_decode(summary);
-        let mut body = maths_utility::escape_decode(body);
-        if Config::get().trim_whitespace {
-            summary = summary.trim().to_string();
-            body = body.trim().to_string();
-        }
+        let cfg = Config::get();
+        // `trim_whitespace` is the most common normalization by far, so it has its own setting,
+        // which goes first.
+        let normalize = |text: String| {
+            let text = if cfg.trim_whitespace {
+                text.trim().to_owned()
+            } else {
+                text
+            };
+            text::normalize(&text, &cfg.text_normalization)
+        };
+        let summary = normalize(maths_utility::escape_decode(summary));
+        let body = normalize(maths_utility::escape_decode(body));
 
         let mut i = 0;
         let mut actions_map = HashMap::new();
@@ -342,36 +537,6 @@ impl Notification {
             i += 2;
         }
 
-        fn image_from_path(path: &str) -> Option<ImageData> {
-            //let start = std::time::Instant::now();
-            //dbg!("Loading image from path...");
-
-            let parts: Vec<&str> = path.split('.').collect();
-            let maybe_image = match parts.last() {
-                // tiny_skia can load pngs and svgs, so we use that where we can, otherwise we
-                // try to load with the image-rs crate.  Hopefully we can move away from that
-                // eventually.
-                Some(ext) => match ext.to_lowercase().as_str() {
-                    "svg" => {
-                        let svg_data = std::fs::read(path).ok()?;
-                        Some(ImageData::SVG(svg_data))
-                    }
-                    "png" => tiny_skia::Pixmap::load_png(path)
-                        .ok()
-                        .map(|p| image::RgbaImage::from_raw(p.width(), p.height(), p.take()))
-                        .map(|i| ImageData::Dynamic(DynamicImage::ImageRgba8(i.unwrap()))),
-                    _ => None,
-                },
-                None => return None,
-            };
-
-            //let end = std::time::Instant::now();
-            //dbg!(end - start);
-
-            // Fall back to trying to open with image-rs.
-            maybe_image.or_else(|| image::open(path).ok().map(ImageData::Dynamic))
-        }
-
         fn image_from_data(data: &VecDeque<Box<dyn RefArg>>) -> Option<ImageData> {
             //let start = std::time::Instant::now();
             //dbg!("Loading image from data...");
@@ -475,17 +640,18 @@ impl Notification {
             percentage = None;
         }
 
-        let cfg = Config::get();
         let timeout = match cfg.zero_timeout_behavior {
-            ZeroTimeoutBehavior::UseDefault => Timeout::Milliseconds(if expire_timeout <= 0 {
-                cfg.timeout
-            } else {
-                expire_timeout
-            }),
+            ZeroTimeoutBehavior::UseDefault => {
+                if expire_timeout <= 0 {
+                    Timeout::Default
+                } else {
+                    Timeout::Milliseconds(expire_timeout)
+                }
+            }
             ZeroTimeoutBehavior::NeverExpire => {
                 // From the spec: https://specifications.freedesktop.org/notification-spec/notification-spec-latest.html
                 if expire_timeout < 0 {
-                    Timeout::Milliseconds(cfg.timeout)
+                    Timeout::Default
                 } else if expire_timeout == 0 {
                     Timeout::NeverExpire
                 } else {
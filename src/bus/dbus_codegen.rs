@@ -126,3 +126,54 @@ where
         );
     })
 }
+
+pub trait IoGithubToqozzWired {
+    fn drop_all(&mut self) -> Result<(), dbus::MethodErr>;
+    fn show_history(&mut self, count: u32) -> Result<(), dbus::MethodErr>;
+    fn list_notifications(&mut self) -> Result<Vec<(u32, String, String, String)>, dbus::MethodErr>;
+    fn reload(&mut self) -> Result<(), dbus::MethodErr>;
+    fn do_not_disturb(&self) -> Result<bool, dbus::MethodErr>;
+    fn set_do_not_disturb(&self, value: bool) -> Result<(), dbus::MethodErr>;
+}
+
+#[derive(Debug)]
+pub struct IoGithubToqozzWiredDoNotDisturbChanged {
+    pub enabled: bool,
+}
+
+impl arg::AppendAll for IoGithubToqozzWiredDoNotDisturbChanged {
+    fn append(&self, i: &mut arg::IterAppend) {
+        arg::RefArg::append(&self.enabled, i);
+    }
+}
+
+impl arg::ReadAll for IoGithubToqozzWiredDoNotDisturbChanged {
+    fn read(i: &mut arg::Iter) -> Result<Self, arg::TypeMismatchError> {
+        Ok(IoGithubToqozzWiredDoNotDisturbChanged { enabled: i.read()? })
+    }
+}
+
+impl dbus::message::SignalArgs for IoGithubToqozzWiredDoNotDisturbChanged {
+    const NAME: &'static str = "DoNotDisturbChanged";
+    const INTERFACE: &'static str = "io.github.toqozz.Wired";
+}
+
+pub fn register_io_github_toqozz_wired<T>(cr: &mut crossroads::Crossroads) -> crossroads::IfaceToken<T>
+where
+    T: IoGithubToqozzWired + Send + 'static,
+{
+    cr.register("io.github.toqozz.Wired", |b| {
+        b.signal::<(bool,), _>("DoNotDisturbChanged", ("enabled",));
+        b.method("DropAll", (), (), |_, t: &mut T, ()| t.drop_all());
+        b.method("ShowHistory", ("count",), (), |_, t: &mut T, (count,)| {
+            t.show_history(count)
+        });
+        b.method("ListNotifications", (), ("notifications",), |_, t: &mut T, ()| {
+            t.list_notifications().map(|x| (x,))
+        });
+        b.method("Reload", (), (), |_, t: &mut T, ()| t.reload());
+        b.property::<bool, _>("DoNotDisturb")
+            .get(|_, t| t.do_not_disturb())
+            .set(|_, t, value| t.set_do_not_disturb(value).map(|_| None));
+    })
+}
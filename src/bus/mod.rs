@@ -1,2 +1,3 @@
+pub mod client;
 pub mod dbus;
 pub mod dbus_codegen;
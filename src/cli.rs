@@ -1,13 +1,23 @@
-use std::io::{self, BufRead, BufReader, ErrorKind, Write};
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, ErrorKind, Read, Write};
+use std::net::Shutdown;
 use std::os::unix::net::{UnixListener, UnixStream};
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use std::process;
 
-use getopts::Options;
+use getopts::{Matches, Options};
 use winit::event_loop::EventLoopWindowTarget;
 use winit::window::WindowId;
 
-use crate::NotifyWindowManager;
+use crate::{
+    bus::{
+        client::NotifyClient,
+        dbus::{Notification, NotificationJson, Timeout, Urgency},
+    },
+    config::ScaleFactor,
+    rendering::offscreen::OffscreenRender,
+    Config, NotifyWindowManager,
+};
 
 pub const SOCKET_PATH: &str = "/tmp/wired.sock";
 
@@ -58,13 +68,18 @@ impl CLIListener {
         Ok(CLIListener { listener })
     }
 
-    pub fn process_messages(&self, manager: &mut NotifyWindowManager, el: &EventLoopWindowTarget<()>) {
+    // Returns true if the config was reloaded because of a message.
+    pub fn process_messages(
+        &self,
+        manager: &mut NotifyWindowManager,
+        el: &EventLoopWindowTarget<()>,
+    ) -> bool {
         // Since we're non-blocking, mostly this is just std::io::ErrorKind::WouldBlock.
         // For other errors, we should probably inform users to aide debugging.
         // I don't love the idea of spamming stderr here, however.
         match self.listener.accept() {
             Ok((socket, _addr)) => match handle_socket_message(manager, el, socket) {
-                Ok(_) => (),
+                Ok(reloaded) => return reloaded,
                 Err(e) => eprintln!("Error while handling socket message: {:?}", e),
             },
             Err(e) => {
@@ -73,13 +88,15 @@ impl CLIListener {
                 }
             }
         }
+
+        false
     }
 }
 
 // Socket stuff:
 fn get_window_id(arg: &str, manager: &NotifyWindowManager) -> Result<WindowId, CLIError> {
     if arg == "latest" {
-        let count = manager.layout_windows.values().flatten().count();
+        let count = manager.unmirrored_windows().len();
 
         if count > 0 {
             manager
@@ -105,11 +122,15 @@ fn get_window_id(arg: &str, manager: &NotifyWindowManager) -> Result<WindowId, C
     }
 }
 
+// Returns true if the config was reloaded.
 pub fn handle_socket_message(
     manager: &mut NotifyWindowManager,
     el: &EventLoopWindowTarget<()>,
     stream: UnixStream,
-) -> Result<(), CLIError> {
+) -> Result<bool, CLIError> {
+    let mut reloaded = false;
+    // Most commands are fire and forget, but some want to hear back.
+    let mut reply = stream.try_clone().map_err(CLIError::Socket)?;
     let stream = BufReader::new(stream);
     for line in stream.lines() {
         let line = match line {
@@ -159,11 +180,7 @@ pub fn handle_socket_message(
                             .parse::<usize>()
                             .map_err(|_| CLIError::Parse("Value is not of type usize."))?;
 
-                        for _ in 0..num {
-                            if let Some(n) = manager.history.pop_back() {
-                                manager.new_notification(n, el);
-                            }
-                        }
+                        manager.show_history(num, el);
                     };
                 }
                 "dnd" => {
@@ -176,6 +193,17 @@ pub fn handle_socket_message(
                 "kill" => {
                     manager.should_exit = true;
                 }
+                "reload" => {
+                    // The client is waiting on a reply, so we let it know how it went.
+                    let response = match Config::reload() {
+                        Ok(_) => {
+                            reloaded = true;
+                            "ok".to_owned()
+                        }
+                        Err(e) => e.to_string(),
+                    };
+                    writeln!(reply, "{}", response).map_err(CLIError::Socket)?;
+                }
                 _ => return Err(CLIError::InvalidCommand),
             }
         } else {
@@ -183,7 +211,7 @@ pub fn handle_socket_message(
         }
     }
 
-    Ok(())
+    Ok(reloaded)
 }
 
 // CLI stuff:
@@ -204,6 +232,152 @@ fn print_version() {
     println!(env!("CARGO_PKG_VERSION"));
 }
 
+// Draw one notification with a layout into an image, rather than a window.
+fn render(
+    layout_name: &str,
+    notification: Option<&str>,
+    output: &str,
+    config_path: Option<PathBuf>,
+    elapsed: i32,
+) -> Result<(), String> {
+    // Unlike the daemon, we don't fall back to the default config if the one we want is broken,
+    // since that wouldn't be the layout that was asked for.
+    let config = match config_path.or_else(Config::installed_config) {
+        Some(path) => Config::load_file(path.clone()).map_err(|e| format!("{}: {}", path.display(), e))?,
+        None => Config::default(),
+    };
+    Config::set(config);
+
+    let notification = match notification {
+        // Either some JSON, or a file to read it from.
+        Some(arg) if arg.trim_start().starts_with('{') => Notification::from_json(arg)?,
+        Some(path) => {
+            let json = std::fs::read_to_string(path).map_err(|e| format!("{}: {}", path, e))?;
+            Notification::from_json(&json)?
+        }
+        None => Notification::from_self(
+            "Summary",
+            "This is the body of a notification.",
+            Default::default(),
+        ),
+    };
+
+    let cfg = Config::get();
+    let layout = match cfg.layouts.iter().find(|l| l.name == layout_name) {
+        Some(l) => l,
+        None => {
+            let names: Vec<&str> = cfg.layouts.iter().map(|l| l.name.as_str()).collect();
+            return Err(format!(
+                "No layout named {}.  Layouts are: {}",
+                layout_name,
+                names.join(", ")
+            ));
+        }
+    };
+
+    // There's no monitor to follow.
+    let scale = match cfg.scale_factor {
+        ScaleFactor::Auto => 1.0,
+        ScaleFactor::Fixed(scale) => scale,
+    };
+    let mut render = OffscreenRender::new(notification, scale);
+    render.set_elapsed(elapsed);
+    render.render(&mut layout.clone());
+
+    let mut file = File::create(output).map_err(|e| format!("{}: {}", output, e))?;
+    render
+        .into_image()
+        .write_to_png(&mut file)
+        .map_err(|e| format!("Couldn't write {}: {}", output, e))
+}
+
+// The daemon won't be running from the same place as us, so it needs an absolute path.
+fn absolute_path(path: &str) -> Result<String, String> {
+    std::fs::canonicalize(path)
+        .map(|p| p.to_string_lossy().into_owned())
+        .map_err(|e| format!("{}: {}", path, e))
+}
+
+// Build a notification from the `--send` options, and send it.  Returns its ID.
+fn send(summary: &str, matches: &Matches) -> Result<u32, String> {
+    let body = matches.opt_str("body").unwrap_or_default();
+    let timeout = match matches.opt_str("timeout") {
+        Some(t) => match t.parse::<i32>() {
+            Ok(ms) if ms < 0 => Timeout::Default,
+            Ok(0) => Timeout::NeverExpire,
+            Ok(ms) => Timeout::Milliseconds(ms),
+            Err(_) => return Err("Timeout must be a whole number of milliseconds.".to_owned()),
+        },
+        None => Timeout::Default,
+    };
+
+    let mut notification = Notification::from_self(summary, &body, timeout);
+    // Act like any other application, rather than like wired talking to itself.
+    notification.urgency = Urgency::Normal;
+    if let Some(app_name) = matches.opt_str("app-name") {
+        notification.app_name = app_name;
+    }
+
+    if let Some(urgency) = matches.opt_str("urgency") {
+        notification.urgency = match urgency.to_lowercase().as_str() {
+            "low" => Urgency::Low,
+            "normal" => Urgency::Normal,
+            "critical" => Urgency::Critical,
+            _ => return Err("Urgency must be one of [low|normal|critical].".to_owned()),
+        };
+    }
+
+    if let Some(progress) = matches.opt_str("progress") {
+        match progress.parse::<u8>() {
+            Ok(p) if p <= 100 => notification.percentage = Some(p as f32 * 0.01),
+            _ => return Err("Progress must be a whole number from 0 to 100.".to_owned()),
+        }
+    }
+
+    notification.tag = matches.opt_str("tag");
+    notification.note = matches.opt_str("note");
+
+    for action in matches.opt_strs("actions") {
+        let (key, label) = action
+            .split_once(':')
+            .ok_or("Actions must be in the format of KEY:LABEL, e.g. default:Open")?;
+        notification.actions.insert(key.to_owned(), label.to_owned());
+    }
+
+    let image = matches.opt_str("image").map(|p| absolute_path(&p)).transpose()?;
+
+    let client = NotifyClient::new().map_err(|e| format!("Couldn't connect to the session bus: {}", e))?;
+    client
+        .send(&notification, image.as_deref())
+        .map_err(|e| format!("Couldn't send notification: {}", e))
+}
+
+// Send every notification in a file of JSON lines, like `print_to_file`'s output, in order.
+// Returns how many were sent.
+fn replay(path: &str) -> Result<usize, String> {
+    let file = File::open(path).map_err(|e| format!("{}: {}", path, e))?;
+    let client = NotifyClient::new().map_err(|e| format!("Couldn't connect to the session bus: {}", e))?;
+
+    let mut sent = 0;
+    for (i, line) in BufReader::new(file).lines().enumerate() {
+        let line = line.map_err(|e| format!("{}: {}", path, e))?;
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        // Line numbers start at 1, like an editor's.
+        let parsed: NotificationJson =
+            serde_json::from_str(&line).map_err(|e| format!("{}:{}: {}", path, i + 1, e))?;
+        let image = parsed.image.as_deref().map(absolute_path).transpose()?;
+        client
+            .send(&parsed.notification, image.as_deref())
+            .map_err(|e| format!("{}:{}: couldn't send notification: {}", path, i + 1, e))?;
+        sent += 1;
+    }
+
+    Ok(sent)
+}
+
 fn validate_identifier(input: &str, allow_all: bool) -> Result<(), &'static str> {
     if input == "latest" || (input == "all" && allow_all) {
         return Ok(());
@@ -252,6 +426,79 @@ pub fn process_cli(args: Vec<String>) -> Result<ShouldRun, String> {
     opts.optopt("s", "show", "show the last N notifications", "N");
     opts.optflag("r", "run", "run the wired daemon");
     opts.optflag("x", "kill", "kill the wired process");
+    opts.optflag("", "reload", "reload the config file, reporting any errors");
+    opts.optflagopt(
+        "",
+        "check-config",
+        "check a config file for problems, without running (defaults to the installed config)",
+        "PATH",
+    );
+    opts.optopt(
+        "",
+        "render",
+        "draw a layout to a PNG without a display server, and leave",
+        "LAYOUT",
+    );
+    opts.optopt(
+        "",
+        "notification",
+        "the notification to --render, as JSON or a JSON file (like print_to_file's output)",
+        "JSON",
+    );
+    opts.optopt(
+        "o",
+        "output",
+        "where to write the --render to (defaults to wired.png)",
+        "PATH",
+    );
+    opts.optopt(
+        "",
+        "config",
+        "the config to --render with (defaults to the installed config)",
+        "PATH",
+    );
+    opts.optopt(
+        "",
+        "elapsed",
+        "how long the notification has been up for when it's --render'ed, in milliseconds",
+        "MS",
+    );
+    opts.optopt(
+        "",
+        "send",
+        "send a notification to the running notification daemon, like notify-send",
+        "SUMMARY",
+    );
+    opts.optopt("", "body", "the body of the notification to --send", "BODY");
+    opts.optopt("", "app-name", "the app name to --send as", "NAME");
+    opts.optopt(
+        "",
+        "urgency",
+        "the urgency of the notification to --send",
+        "[low|normal|critical]",
+    );
+    opts.optopt("", "progress", "the progress to --send, from 0 to 100", "N");
+    opts.optopt("", "tag", "the wired-tag to --send", "TAG");
+    opts.optopt("", "note", "the wired-note to --send", "NOTE");
+    opts.optmulti(
+        "",
+        "actions",
+        "an action to --send, can be given more than once",
+        "KEY:LABEL",
+    );
+    opts.optopt(
+        "",
+        "timeout",
+        "how long the notification to --send lasts, in milliseconds (0 to never expire)",
+        "MS",
+    );
+    opts.optopt("", "image", "an image to --send", "PATH");
+    opts.optopt(
+        "",
+        "replay",
+        "send each notification in a file, like print_to_file's output",
+        "PATH",
+    );
     opts.optflag("v", "version", "print the version of wired and leave");
     let matches = match opts.parse(&args[1..]) {
         Ok(m) => m,
@@ -276,12 +523,83 @@ pub fn process_cli(args: Vec<String>) -> Result<ShouldRun, String> {
         return Ok(ShouldRun::Yes);
     }
 
+    if matches.opt_present("check-config") {
+        // getopts only takes optional values in the `--check-config=PATH` form, so we also
+        // accept a path as a free argument.
+        let path = match matches
+            .opt_str("check-config")
+            .or_else(|| matches.free.first().cloned())
+        {
+            Some(p) => PathBuf::from(p),
+            None => Config::installed_config().ok_or("Couldn't find a config file to check.")?,
+        };
+
+        let problems = Config::check_file(&path).map_err(|e| e.to_string())?;
+        if problems.is_empty() {
+            println!("{}: no problems found.", path.display());
+            return Ok(ShouldRun::No);
+        }
+
+        // Formatted like compiler errors, so editors can jump straight to them.
+        for problem in &problems {
+            let file = problem.file.as_ref().unwrap_or(&path);
+            match problem.position {
+                Some(_) => eprintln!("{}:{}", file.display(), problem),
+                None => eprintln!("{}: {}", file.display(), problem),
+            }
+        }
+        eprintln!("Found {} problem(s).", problems.len());
+        process::exit(1);
+    }
+
+    if let Some(layout) = matches.opt_str("render") {
+        let output = matches.opt_str("o").unwrap_or_else(|| "wired.png".to_owned());
+        let elapsed = match matches.opt_str("elapsed").map(|e| e.parse::<i32>()) {
+            Some(Ok(ms)) if ms >= 0 => ms,
+            Some(_) => return Err("Elapsed must be a whole number of milliseconds.".to_owned()),
+            None => 0,
+        };
+        // Scripts and CI check the exit code, so a failed render shouldn't look like a success.
+        if let Err(e) = render(
+            &layout,
+            matches.opt_str("notification").as_deref(),
+            &output,
+            matches.opt_str("config").map(PathBuf::from),
+            elapsed,
+        ) {
+            eprintln!("{}", e);
+            process::exit(1);
+        }
+        println!("Rendered {} to {}.", layout, output);
+        return Ok(ShouldRun::No);
+    }
+
+    if let Some(summary) = matches.opt_str("send") {
+        // As with `--render`, scripts should be able to tell when nothing was sent.
+        let id = send(&summary, &matches).unwrap_or_else(|e| {
+            eprintln!("{}", e);
+            process::exit(1)
+        });
+        println!("{}", id);
+        return Ok(ShouldRun::No);
+    }
+
+    if let Some(path) = matches.opt_str("replay") {
+        let sent = replay(&path).unwrap_or_else(|e| {
+            eprintln!("{}", e);
+            process::exit(1)
+        });
+        println!("Sent {} notification(s).", sent);
+        return Ok(ShouldRun::No);
+    }
+
     // All these options use a socket.
     if matches.opt_present("d")
         || matches.opt_present("a")
         || matches.opt_present("s")
         || matches.opt_present("z")
         || matches.opt_present("x")
+        || matches.opt_present("reload")
     {
         let mut sock = match UnixStream::connect(SOCKET_PATH) {
             Ok(s) => s,
@@ -339,6 +657,20 @@ pub fn process_cli(args: Vec<String>) -> Result<ShouldRun, String> {
             sock.write(format!("show:{}", to_show).as_bytes())
                 .map_err(|e| e.to_string())?;
         }
+
+        // This has to go last, because we stop writing to wait on the reply.
+        if matches.opt_present("reload") {
+            sock.write("reload:".as_bytes()).map_err(|e| e.to_string())?;
+            sock.shutdown(Shutdown::Write).map_err(|e| e.to_string())?;
+
+            let mut response = String::new();
+            sock.read_to_string(&mut response).map_err(|e| e.to_string())?;
+            match response.trim() {
+                "ok" => println!("Config reloaded."),
+                "" => return Err("Didn't get a reply from the wired daemon.".to_owned()),
+                problem => return Err(problem.to_owned()),
+            }
+        }
     }
 
     Ok(ShouldRun::No)
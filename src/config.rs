@@ -2,23 +2,27 @@
 #![allow(static_mut_refs)]
 
 use std::{
+    collections::HashMap,
     env,
     fmt::{self, Display, Formatter},
     io,
-    path::PathBuf,
+    marker::PhantomData,
+    path::{Path, PathBuf},
     sync::mpsc::{self, Receiver},
     time::Duration,
 };
 
+use home_dir::HomeDirExt;
 use notify::{DebouncedEvent, RecommendedWatcher, RecursiveMode, Watcher};
 use serde::{
-    de::{self, Deserializer, Unexpected},
+    de::{self, DeserializeOwned, Deserializer, Unexpected},
     Deserialize,
 };
 
 use crate::{
-    maths_utility::{self, Rect, Vec2},
+    maths_utility::{self, Easing, Rect, Vec2},
     rendering::layout::{LayoutBlock, LayoutElement},
+    rendering::text::{self, TextNormalization},
 };
 
 // Workaround for rust not allowing contcatenations of str constants yet:
@@ -40,7 +44,7 @@ pub enum Error {
     // Config file not found.
     NotFound,
     // Validation error.
-    Validate(&'static str),
+    Validate(String),
     // Bad hex string error.
     Hexadecimal(&'static str),
     // IO error reading file.
@@ -49,6 +53,8 @@ pub enum Error {
     Ron(ron::de::Error),
     // Watch error.
     Watch(notify::Error),
+    // Error in a file pulled in by `include`.
+    Include(PathBuf, Box<Error>),
 }
 
 impl std::error::Error for Error {
@@ -60,6 +66,7 @@ impl std::error::Error for Error {
             Error::Io(err) => err.source(),
             Error::Ron(err) => err.source(),
             Error::Watch(err) => err.source(),
+            Error::Include(_, err) => err.source(),
         }
     }
 }
@@ -75,6 +82,49 @@ impl Display for Error {
             Error::Io(err) => write!(f, "Error reading config file: {}", err),
             Error::Ron(err) => write!(f, "Problem with config file: {}", err),
             Error::Watch(err) => write!(f, "Error watching config directory: {}", err),
+            Error::Include(path, err) => write!(f, "In included file {}: {}", path.display(), err),
+        }
+    }
+}
+
+// A problem found while checking a config, see `Config::check_str`.
+#[derive(Debug)]
+pub struct Problem {
+    // The file the problem is in, if it isn't the one being checked (i.e. it was included).
+    pub file: Option<PathBuf>,
+    // Line and column (both starting from 1), when we can figure out where the problem is.
+    pub position: Option<(usize, usize)>,
+    pub message: String,
+}
+
+impl Problem {
+    fn from_error(mut file: Option<PathBuf>, mut err: Error) -> Self {
+        // Dig down to the file that actually has the problem.
+        while let Error::Include(path, inner) = err {
+            file = Some(path);
+            err = *inner;
+        }
+
+        match err {
+            Error::Ron(e) => Problem {
+                file,
+                position: (e.position.line > 0).then_some((e.position.line, e.position.col)),
+                message: e.code.to_string(),
+            },
+            e => Problem {
+                file,
+                position: None,
+                message: e.to_string(),
+            },
+        }
+    }
+}
+
+impl Display for Problem {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self.position {
+            Some((line, col)) => write!(f, "{}:{}: {}", line, col, self.message),
+            None => write!(f, "{}", self.message),
         }
     }
 }
@@ -82,20 +132,33 @@ impl Display for Error {
 pub struct ConfigWatcher {
     watcher: RecommendedWatcher,
     receiver: Receiver<DebouncedEvent>,
+    // The main config file.  We always watch this, even if it didn't load.
+    path: PathBuf,
+    // The files that the current config was loaded from, as of the last time we checked.
+    config_files: Vec<PathBuf>,
+    // Every file we're watching, in the form that events will be reported in.
+    files: Vec<PathBuf>,
+    // We watch directories rather than files, because editors like to replace files when saving.
+    dirs: Vec<PathBuf>,
 }
 
 impl ConfigWatcher {
     // Returns true if config was updated, false if not.
-    pub fn check_and_update_config(&self) -> bool {
+    pub fn check_and_update_config(&mut self) -> bool {
+        // The config may have been reloaded from elsewhere, and it may include different files now.
+        if self.config_files != Config::get().files {
+            self.update_watches();
+        }
+
         if let Ok(ev) = self.receiver.try_recv() {
             match ev {
                 DebouncedEvent::Write(p) | DebouncedEvent::Create(p) | DebouncedEvent::Chmod(p) => {
-                    if let Some(file_name) = p.file_name() {
-                        // Make sure the file that was changed is our file, since we watch the
-                        // entire directory.
-                        if file_name == CONFIG_FILENAME!() && Config::try_reload(p) {
-                            return true;
-                        }
+                    // Make sure the file that was changed is one of ours, since we watch entire
+                    // directories.
+                    let is_ours = self.files.contains(&p);
+                    if is_ours && Config::try_reload(self.path.clone()).is_ok() {
+                        self.update_watches();
+                        return true;
                     }
                 }
 
@@ -105,21 +168,70 @@ impl ConfigWatcher {
 
         false
     }
+
+    // Watch the main config file, and every file the current config was loaded from.
+    fn update_watches(&mut self) {
+        self.config_files = Config::get().files.clone();
+
+        let mut files = vec![];
+        for path in std::iter::once(&self.path).chain(self.config_files.iter()) {
+            let dir = match path.parent() {
+                Some(d) if !d.as_os_str().is_empty() => d,
+                _ => Path::new("."),
+            };
+            // Events come in relative to the directory we're watching, so match that.
+            if let (Ok(dir), Some(file_name)) = (std::fs::canonicalize(dir), path.file_name()) {
+                files.push(dir.join(file_name));
+            }
+        }
+
+        let mut dirs: Vec<PathBuf> = files
+            .iter()
+            .filter_map(|f| f.parent())
+            .map(Path::to_owned)
+            .collect();
+        dirs.sort();
+        dirs.dedup();
+
+        for dir in self.dirs.iter().filter(|d| !dirs.contains(d)) {
+            let _ = self.watcher.unwatch(dir);
+        }
+        for dir in dirs.iter().filter(|d| !self.dirs.contains(d)) {
+            if let Err(e) = self.watcher.watch(dir, RecursiveMode::NonRecursive) {
+                println!("Couldn't watch {} for config changes: {}", dir.display(), e);
+            }
+        }
+
+        self.files = files;
+        self.dirs = dirs;
+    }
 }
 
 #[derive(Debug, Deserialize)]
 pub struct Config {
+    // Other config files to load on top of this one, see `IncludedConfig`.
+    #[serde(default)]
+    pub include: Vec<String>,
+
     // Maximum number of notifications to show on screen at once.
     pub max_notifications: usize,
     pub timeout: i32,       // Default timeout, in milliseconds.
     pub poll_interval: u64, // Time between checking for updates, events, drawing, etc.
     #[serde(default = "maths_utility::val_500")]
     pub idle_poll_interval: u64, // Same as above, but when no notifications are present.
+    // Named colors, fonts and paddings that layout blocks can refer to, see `Styles`.
+    #[serde(default)]
+    pub styles: Styles,
+    // Can be left out when blocks are included from other files.
+    #[serde(default)]
     pub layout_blocks: Vec<LayoutBlock>,
 
     // How to handle various DBus expire_timeout values
     #[serde(default)]
     pub zero_timeout_behavior: ZeroTimeoutBehavior,
+    // Default timeouts for each urgency, and limits on the timeouts that apps ask for.
+    #[serde(default)]
+    pub timeouts: TimeoutPolicy,
 
     // Optional Properties
 
@@ -140,6 +252,13 @@ pub struct Config {
     #[serde(default)]
     pub unpause_on_input: bool,
 
+    // Pause a notification's timeout while the cursor is over it.
+    #[serde(default)]
+    pub pause_on_hover: bool,
+    // How long to wait after the cursor leaves before the timeout carries on, in milliseconds.
+    #[serde(default)]
+    pub hover_grace_period: u64,
+
     // Enable/disable notification replace functionality.  I don't like how some apps do it.
     #[serde(default = "maths_utility::val_true")]
     pub replacing_enabled: bool,
@@ -172,8 +291,20 @@ pub struct Config {
     #[serde(default)]
     pub min_window_height: u32,
 
+    // Layouts are in logical pixels, which are scaled up by this.  `Auto` uses the scale factor of
+    // the monitor a notification is shown on (for HiDPI monitors).
+    #[serde(default)]
+    pub scale_factor: ScaleFactor,
+
+    // Whether to draw with X11 windows or Wayland layer surfaces.  Only read on startup.
+    #[serde(default)]
+    pub backend: Backend,
+
     #[serde(default = "maths_utility::val_true")]
     pub trim_whitespace: bool,
+    // Applied in order to the summary and body of every notification, after `trim_whitespace`.
+    #[serde(default)]
+    pub text_normalization: Vec<TextNormalization>,
 
     // Draws rectangles around elements.
     #[serde(default)]
@@ -186,6 +317,10 @@ pub struct Config {
     #[serde(default)]
     pub shortcuts: ShortcutsConfig,
 
+    // Slide and fade transitions for notifications.  Disabled by default.
+    #[serde(default)]
+    pub animations: Animations,
+
     #[serde(skip)] // derived from user settings
     pub(crate) layouts: Vec<LayoutBlock>,
 
@@ -194,6 +329,111 @@ pub struct Config {
 
     #[serde(skip)] // derived from icon_theme at load time
     pub(crate) icon_theme_chain: Vec<String>,
+
+    #[serde(skip)] // every file this config was loaded from, including the main one
+    pub(crate) files: Vec<PathBuf>,
+}
+
+// A config file pulled in by another config's `include`.
+// Everything here is optional: `layout_blocks` are added to the including config's, and any other
+// settings override the including config's.
+#[derive(Debug, Deserialize)]
+struct IncludedConfig {
+    #[serde(default)]
+    include: Vec<String>,
+    #[serde(default)]
+    styles: Styles,
+    #[serde(default)]
+    layout_blocks: Vec<LayoutBlock>,
+
+    max_notifications: Option<usize>,
+    timeout: Option<i32>,
+    poll_interval: Option<u64>,
+    idle_poll_interval: Option<u64>,
+    zero_timeout_behavior: Option<ZeroTimeoutBehavior>,
+    timeouts: Option<TimeoutPolicy>,
+    idle_threshold: Option<u64>,
+    icon_theme: Option<String>,
+    notify_on_reload: Option<bool>,
+    notifications_spawn_paused: Option<bool>,
+    unpause_on_input: Option<bool>,
+    pause_on_hover: Option<bool>,
+    hover_grace_period: Option<u64>,
+    replacing_enabled: Option<bool>,
+    replacing_resets_timeout: Option<bool>,
+    closing_enabled: Option<bool>,
+    history_length: Option<usize>,
+    focus_follows: Option<FollowMode>,
+    print_to_file: Option<String>,
+    min_window_width: Option<u32>,
+    min_window_height: Option<u32>,
+    scale_factor: Option<ScaleFactor>,
+    backend: Option<Backend>,
+    trim_whitespace: Option<bool>,
+    text_normalization: Option<Vec<TextNormalization>>,
+    debug: Option<bool>,
+    debug_color: Option<Color>,
+    debug_color_alt: Option<Color>,
+    shortcuts: Option<ShortcutsConfig>,
+    animations: Option<Animations>,
+}
+
+impl IncludedConfig {
+    // Merge into `config`, and hand back whatever this file includes in turn.
+    fn merge_into(self, config: &mut Config) -> Vec<String> {
+        macro_rules! override_settings {
+            ($($field:ident),* $(,)?) => {
+                $(
+                    if let Some(value) = self.$field {
+                        config.$field = value;
+                    }
+                )*
+            };
+        }
+
+        override_settings!(
+            max_notifications,
+            timeout,
+            poll_interval,
+            idle_poll_interval,
+            zero_timeout_behavior,
+            timeouts,
+            icon_theme,
+            notify_on_reload,
+            notifications_spawn_paused,
+            unpause_on_input,
+            pause_on_hover,
+            hover_grace_period,
+            replacing_enabled,
+            replacing_resets_timeout,
+            closing_enabled,
+            history_length,
+            focus_follows,
+            min_window_width,
+            min_window_height,
+            scale_factor,
+            backend,
+            trim_whitespace,
+            text_normalization,
+            debug,
+            debug_color,
+            debug_color_alt,
+            shortcuts,
+            animations,
+        );
+
+        // These are optional in `Config` too.
+        if self.idle_threshold.is_some() {
+            config.idle_threshold = self.idle_threshold;
+        }
+        if self.print_to_file.is_some() {
+            config.print_to_file = self.print_to_file;
+        }
+
+        config.styles.merge(self.styles);
+        config.layout_blocks.extend(self.layout_blocks);
+        self.include
+    }
 }
 
 impl Config {
@@ -269,6 +509,13 @@ impl Config {
         }
     }
 
+    // Use `config` from now on, without watching any file, e.g. for `--render`.
+    pub fn set(config: Config) {
+        unsafe {
+            CONFIG = Some(config);
+        }
+    }
+
     // Get immutable reference to global config variable.
     pub fn get() -> &'static Config {
         unsafe {
@@ -291,25 +538,36 @@ impl Config {
 
     // Attempt to load the config again.
     // If we can, then replace the existing config.
-    // If we can't, then do nothing.
-    pub fn try_reload(path: PathBuf) -> bool {
+    // If we can't, then do nothing, and hand the error back so it can be reported.
+    pub fn try_reload(path: PathBuf) -> Result<(), Error> {
         match Config::load_file(path) {
             Ok(cfg) => {
                 unsafe {
                     CONFIG = Some(cfg);
                 }
                 println!("Config reloaded.");
-                true
+                Ok(())
             }
             Err(e) => {
                 println!("Tried to reload the config but couldn't: {}", e);
-                false
+                Err(e)
+            }
+        }
+    }
+
+    // Reload the config from wherever we would normally find it.
+    pub fn reload() -> Result<(), Error> {
+        match Config::installed_config() {
+            Some(path) => Config::try_reload(path),
+            None => {
+                println!("Tried to reload the config but couldn't find one.");
+                Err(Error::NotFound)
             }
         }
     }
 
     // https://github.com/alacritty/alacritty/blob/f14d24542c3ceda3b508c707eb79cf2fe2a04bd1/alacritty/src/config/mod.rs#L98
-    fn installed_config() -> Option<PathBuf> {
+    pub fn installed_config() -> Option<PathBuf> {
         xdg::BaseDirectories::with_prefix("wired")
             .ok()
             .and_then(|xdg| xdg.find_config_file(CONFIG_FILENAME!()))
@@ -339,47 +597,235 @@ impl Config {
 
     // Load config or return error.
     pub fn load_file(path: PathBuf) -> Result<Self, Error> {
-        let cfg_string = std::fs::read_to_string(path);
-        let cfg_string = match cfg_string {
-            Ok(mut string) => {
-                string.insert_str(0, "#![enable(implicit_some)]\n");
-                string
+        Config::parse_file(&path).and_then(|(config, _)| Config::transform_and_validate(config))
+    }
+
+    pub fn load_str(cfg_str: &str) -> Result<Self, Error> {
+        let config: Config = parse_ron(cfg_str)?;
+        // Includes are relative to the file they're in, so there's nothing to resolve them against.
+        if !config.include.is_empty() {
+            return Err(Error::Validate(
+                "`include` can only be used in a config file.".to_owned(),
+            ));
+        }
+
+        Config::transform_and_validate(config)
+    }
+
+    // Deserialize a config file and everything it includes, without any validation.
+    // The contents of each file are handed back too, so that problems can be located later.
+    fn parse_file(path: &Path) -> Result<(Self, Vec<(PathBuf, String)>), Error> {
+        let source = std::fs::read_to_string(path).map_err(Error::Io)?;
+        let mut config: Config = parse_ron(&source)?;
+        let mut sources = vec![(path.to_owned(), source)];
+
+        let includes = std::mem::take(&mut config.include);
+        config.include_files(path, includes, &mut sources)?;
+
+        config.files = sources.iter().map(|(p, _)| p.clone()).collect();
+        Ok((config, sources))
+    }
+
+    // Merge each included file into this config, in order, followed by anything they include.
+    fn include_files(
+        &mut self,
+        from: &Path,
+        includes: Vec<String>,
+        sources: &mut Vec<(PathBuf, String)>,
+    ) -> Result<(), Error> {
+        for include in includes {
+            let path = resolve_include(from, &include)?;
+
+            // Files can be included more than once, or even include each other; only the first
+            // include counts.
+            if sources.iter().any(|(p, _)| is_same_file(p, &path)) {
+                continue;
             }
-            Err(e) => return Err(Error::Io(e)),
-        };
 
-        Config::load_str(cfg_string.as_str())
+            let wrap = |e| Error::Include(path.clone(), Box::new(e));
+            let source = std::fs::read_to_string(&path).map_err(|e| wrap(Error::Io(e)))?;
+            let included: IncludedConfig = parse_ron(&source).map_err(wrap)?;
+            sources.push((path.clone(), source));
+
+            let nested = included.merge_into(self);
+            self.include_files(&path, nested, sources).map_err(wrap)?;
+        }
+
+        Ok(())
     }
 
-    pub fn load_str(cfg_str: &str) -> Result<Self, Error> {
-        // Really ugly and annoying hack because ron doesn't allow implicit some by
-        // default.
-        let string = format!("#![enable(implicit_some)]\n{}", cfg_str);
-        let config: Result<Self, _> = ron::de::from_str(string.as_str());
-        match config {
-            Ok(cfg) => Config::transform_and_validate(cfg),
-            Err(e) => Err(Error::Ron(e)),
+    // Fully parse and validate a config file, collecting as many problems as we can find rather
+    // than stopping at the first one.
+    // This goes further than loading does: fonts we can't find and colors that are out of range
+    // won't stop a config from loading, but they're almost certainly mistakes.
+    // Only fails if the file itself can't be read.
+    pub fn check_file(path: &Path) -> Result<Vec<Problem>, Error> {
+        let (mut config, sources) = match Config::parse_file(path) {
+            Ok(c) => c,
+            Err(Error::Io(e)) => return Err(Error::Io(e)),
+            // Nothing else is checkable if we can't parse the file.
+            Err(e) => return Ok(vec![Problem::from_error(None, e)]),
+        };
+
+        // Problems with blocks can be in any of the files, so go looking for them.
+        let block_problem = |name: &str, message: String| {
+            for (file, source) in &sources {
+                if let Some(position) = find_block_position(source, name) {
+                    return Problem {
+                        file: (file != path).then(|| file.clone()),
+                        position: Some(position),
+                        message,
+                    };
+                }
+            }
+
+            Problem {
+                file: None,
+                position: None,
+                message,
+            }
+        };
+
+        let mut problems: Vec<Problem> = find_layout_problems(&config.layout_blocks)
+            .into_iter()
+            .chain(find_layout_warnings(&config.layout_blocks))
+            .chain(find_override_problems(&mut config.layout_blocks))
+            .chain(resolve_styles(&mut config))
+            .map(|(name, message)| block_problem(&name, message))
+            .collect();
+
+        for block in &mut config.layout_blocks {
+            let override_fonts = block
+                .urgency_overrides
+                .iter_mut()
+                .flat_map(|(_, o)| o.fonts.values_mut());
+            for font in block
+                .params
+                .fonts_mut()
+                .into_iter()
+                .map(|(_, f)| f)
+                .chain(override_fonts)
+            {
+                if let Err(problem) = text::check_font(font) {
+                    problems.push(block_problem(
+                        &block.name,
+                        format!("LayoutBlock \"{}\": {}", block.name, problem),
+                    ));
+                }
+            }
+
+            if let LayoutElement::NotificationBlock(p) = &mut block.params {
+                for (field, fill) in [
+                    ("background_fill", &mut p.background_fill),
+                    ("border_fill", &mut p.border_fill),
+                ] {
+                    if let Some(Err(problem)) = fill.as_mut().map(|f| f.load()) {
+                        problems.push(block_problem(
+                            &block.name,
+                            format!("LayoutBlock \"{}\": `{}` {}", block.name, field, problem),
+                        ));
+                    }
+                }
+            }
+
+            for (field, color) in block.params.colors_mut() {
+                if let Err(problem) = color.check() {
+                    problems.push(block_problem(
+                        &block.name,
+                        format!("LayoutBlock \"{}\": `{}` {}", block.name, field, problem),
+                    ));
+                }
+            }
+
+            for (urgency, overrides) in block.urgency_overrides.iter_mut() {
+                for (field, color) in &overrides.colors {
+                    if let Err(problem) = color.check() {
+                        problems.push(block_problem(
+                            &block.name,
+                            format!(
+                                "LayoutBlock \"{}\": `urgency_overrides.{}.{}` {}",
+                                block.name, urgency, field, problem
+                            ),
+                        ));
+                    }
+                }
+            }
         }
+
+        for (field, color) in [
+            ("debug_color", &config.debug_color),
+            ("debug_color_alt", &config.debug_color_alt),
+        ] {
+            if let Err(problem) = color.check() {
+                problems.push(Problem {
+                    file: None,
+                    position: None,
+                    message: format!("`{}` {}", field, problem),
+                });
+            }
+        }
+
+        // Catch anything else that would stop the config from loading.
+        if problems.is_empty() {
+            if let Err(e) = Config::transform_and_validate(config) {
+                problems.push(Problem::from_error(None, e));
+            }
+        }
+
+        Ok(problems)
     }
 
     pub fn transform_and_validate(mut config: Config) -> Result<Self, Error> {
-        // NOTE: we might actually want to search for the "root" text.
-        if config.layout_blocks.is_empty() {
-            return Err(Error::Validate("Config did not contain any layout blocks!"));
+        if let Some((_, problem)) = find_layout_problems(&config.layout_blocks).into_iter().next() {
+            return Err(Error::Validate(problem));
         }
 
-        // Look for children of current root.
-        // If child found, insert it and then look for children of that node.
-        let mut blocks = config.layout_blocks;
-        // Check that all blocks are unique.
-        for i in 0..blocks.len() - 1 {
-            for j in 0..blocks.len() {
-                if blocks[i].name == blocks[j].name && i != j {
-                    return Err(Error::Validate("All LayoutBlocks must have unique names!"));
+        // Blocks that don't lead back to a root are just left out of the tree, so they don't stop
+        // the config from loading.  `--check-config` still reports them.
+        for (_, problem) in find_layout_warnings(&config.layout_blocks) {
+            eprintln!("Warning: {}", problem);
+        }
+
+        if let Some((_, problem)) = find_override_problems(&mut config.layout_blocks)
+            .into_iter()
+            .next()
+        {
+            return Err(Error::Validate(problem));
+        }
+
+        if let Some((_, problem)) = resolve_styles(&mut config).into_iter().next() {
+            return Err(Error::Validate(problem));
+        }
+
+        // Fills that can't be drawn (e.g. an image that's missing) are left out, rather than
+        // stopping the config from loading.
+        for block in &mut config.layout_blocks {
+            if let LayoutElement::NotificationBlock(p) = &mut block.params {
+                for (field, fill) in [
+                    ("background_fill", &mut p.background_fill),
+                    ("border_fill", &mut p.border_fill),
+                ] {
+                    if let Some(Err(problem)) = fill.as_mut().map(|f| f.load()) {
+                        eprintln!("LayoutBlock \"{}\": `{}` {}", block.name, field, problem);
+                        *fill = None;
+                    }
                 }
             }
         }
 
+        if let ScaleFactor::Fixed(scale) = config.scale_factor {
+            if scale <= 0.0 {
+                return Err(Error::Validate(format!(
+                    "scale_factor must be above 0, got {}.",
+                    scale
+                )));
+            }
+        }
+
+        // Look for children of current root.
+        // If child found, insert it and then look for children of that node.
+        let mut blocks = config.layout_blocks;
+
         let mut roots: Vec<LayoutBlock> = vec![];
         let mut i = 0;
         while i < blocks.len() {
@@ -415,29 +861,17 @@ impl Config {
             remaining
         }
 
+        // `find_layout_problems` guarantees that every root is a NotificationBlock.  Anything left
+        // over afterwards is orphaned, and gets dropped.
         for mut root in roots {
             blocks = find_and_add_children(&mut root, blocks);
-
-            match root.params {
-                LayoutElement::NotificationBlock(_) => {
-                    config.layouts.push(root);
-                }
-                _ => {
-                    return Err(Error::Validate(
-                        "Root LayoutBlock params must be of type NotificationBlock!",
-                    ))
-                }
-            }
-        }
-
-        if !blocks.is_empty() && config.debug {
-            eprintln!("There were {} blocks remaining after creating the layout tree.  Something must be wrong here.", blocks.len());
+            config.layouts.push(root);
         }
 
         config.is_auto_active_monitor = config
             .layouts
             .iter()
-            .any(|layout| layout.as_notification_block().monitor < 0);
+            .any(|layout| layout.as_notification_block().follows_active_monitor());
 
         // Cache theme chain to avoid (some) recursive lookup every time we search for an icon.
         config.icon_theme_chain = crate::icons::build_theme_chain(&config.icon_theme);
@@ -445,9 +879,9 @@ impl Config {
         Ok(config)
     }
 
-    // Watch config file for changes, and send message to `Configwatcher` when something
-    // happens.
-    pub fn watch(mut path: PathBuf) -> Result<ConfigWatcher, Error> {
+    // Watch config file (and anything it includes) for changes, and send message to
+    // `Configwatcher` when something happens.
+    pub fn watch(path: PathBuf) -> Result<ConfigWatcher, Error> {
         let (sender, receiver) = mpsc::channel();
 
         // Duration is a debouncing period.
@@ -455,16 +889,289 @@ impl Config {
             notify::watcher(sender, Duration::from_millis(10)).expect("Unable to spawn file watcher.");
 
         // Watch dir.
-        path.pop();
-        let path = std::fs::canonicalize(path).expect("Couldn't canonicalize path, wtf.");
-        let result = watcher.watch(path, RecursiveMode::NonRecursive);
+        let dir = path.parent().expect("Config file has no parent directory, wtf.");
+        let dir = std::fs::canonicalize(dir).expect("Couldn't canonicalize path, wtf.");
+        let result = watcher.watch(&dir, RecursiveMode::NonRecursive);
         match result {
-            Ok(_) => Ok(ConfigWatcher { watcher, receiver }),
+            Ok(_) => {
+                let mut cw = ConfigWatcher {
+                    watcher,
+                    receiver,
+                    path,
+                    config_files: vec![],
+                    files: vec![],
+                    dirs: vec![dir],
+                };
+                cw.update_watches();
+                Ok(cw)
+            }
             Err(e) => Err(Error::Watch(e)),
         }
     }
 }
 
+// Deserialize some config text, without any validation.
+fn parse_ron<T: DeserializeOwned>(cfg_str: &str) -> Result<T, Error> {
+    // Really ugly and annoying hack because ron doesn't allow implicit some by
+    // default.
+    let string = format!("#![enable(implicit_some)]\n{}", cfg_str);
+    let config: Result<T, ron::de::Error> = ron::de::from_str(string.as_str());
+    config.map_err(|mut e| {
+        // Account for the line we snuck in above, so positions match the user's file.
+        // Errors from our own deserialize impls don't have a position at all (0:0).
+        if e.position.line > 1 {
+            e.position.line -= 1;
+        }
+        Error::Ron(e)
+    })
+}
+
+// Includes are relative to the file they're in, and support tilde expansion.
+fn resolve_include(from: &Path, include: &str) -> Result<PathBuf, Error> {
+    let path = Path::new(include)
+        .expand_home()
+        .map_err(|e| Error::Validate(format!("Failed tilde expansion of \"{}\": {}", include, e)))?;
+
+    match from.parent() {
+        Some(dir) if path.is_relative() => Ok(dir.join(path)),
+        _ => Ok(path),
+    }
+}
+
+fn is_same_file(a: &Path, b: &Path) -> bool {
+    match (std::fs::canonicalize(a), std::fs::canonicalize(b)) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => a == b,
+    }
+}
+
+// Finds problems that stop a layout tree being built at all: no blocks, duplicate names, and
+// roots that aren't NotificationBlocks.
+// Each problem is paired with the name of the block it belongs to, so that it can be located later.
+fn find_layout_problems(blocks: &[LayoutBlock]) -> Vec<(String, String)> {
+    let mut problems = vec![];
+    if blocks.is_empty() {
+        problems.push((
+            "".to_owned(),
+            "Config did not contain any layout blocks!".to_owned(),
+        ));
+        return problems;
+    }
+
+    let mut by_name: HashMap<&str, &LayoutBlock> = HashMap::new();
+    for block in blocks {
+        if by_name.insert(&block.name, block).is_some() {
+            problems.push((
+                block.name.clone(),
+                format!(
+                    "There is more than one LayoutBlock named \"{}\"; all LayoutBlocks must have unique names.",
+                    block.name
+                ),
+            ));
+        }
+    }
+
+    for block in blocks {
+        let is_notification = matches!(block.params, LayoutElement::NotificationBlock(_));
+        if block.parent.is_empty() && !is_notification {
+            problems.push((
+                block.name.clone(),
+                format!(
+                    "LayoutBlock \"{}\" has no parent, but root LayoutBlock params must be of type NotificationBlock.",
+                    block.name
+                ),
+            ));
+        }
+    }
+
+    problems
+}
+
+// Finds problems that the config can still load with: NotificationBlocks that aren't roots, and
+// blocks that can never be reached from a root (those whose parents don't exist, or that are part
+// of or below a cycle).  Paired with block names, like `find_layout_problems`.
+fn find_layout_warnings(blocks: &[LayoutBlock]) -> Vec<(String, String)> {
+    let by_name: HashMap<&str, &LayoutBlock> = blocks.iter().map(|b| (b.name.as_str(), b)).collect();
+
+    let mut problems = vec![];
+    for block in blocks {
+        let is_notification = matches!(block.params, LayoutElement::NotificationBlock(_));
+        if !block.parent.is_empty() && is_notification {
+            problems.push((
+                block.name.clone(),
+                format!(
+                    "NotificationBlock \"{}\" has a parent, but NotificationBlocks are meant to be used as a root.",
+                    block.name
+                ),
+            ));
+        }
+
+        // Walk up the tree until we get to a root, or find out why we can't.
+        let mut visited = vec![block.name.as_str()];
+        let mut current = block;
+        while !current.parent.is_empty() {
+            let parent = match by_name.get(current.parent.as_str()) {
+                Some(p) => p,
+                None => {
+                    let problem = if current.name == block.name {
+                        format!(
+                            "LayoutBlock \"{}\" has parent \"{}\", but there is no LayoutBlock with that name.",
+                            block.name, block.parent
+                        )
+                    } else {
+                        format!(
+                            "LayoutBlock \"{}\" is orphaned, because its ancestor \"{}\" has a parent that doesn't exist (\"{}\").",
+                            block.name, current.name, current.parent
+                        )
+                    };
+                    problems.push((block.name.clone(), format!("{}  It won't be drawn.", problem)));
+                    break;
+                }
+            };
+
+            if visited.contains(&parent.name.as_str()) {
+                let problem = if parent.name == block.name {
+                    visited.push(&parent.name);
+                    format!(
+                        "LayoutBlock \"{}\" is part of a cycle: {}.",
+                        block.name,
+                        visited.join(" -> ")
+                    )
+                } else {
+                    format!(
+                        "LayoutBlock \"{}\" is orphaned, because its ancestors form a cycle.",
+                        block.name
+                    )
+                };
+                problems.push((block.name.clone(), format!("{}  It won't be drawn.", problem)));
+                break;
+            }
+
+            visited.push(&parent.name);
+            current = parent;
+        }
+    }
+
+    problems
+}
+
+// Replaces references to `styles` with the values they name.
+// Like `find_layout_problems`, each problem is paired with the name of the block it belongs to.
+fn resolve_styles(config: &mut Config) -> Vec<(String, String)> {
+    let styles = &config.styles;
+    let mut problems = vec![];
+
+    for block in &mut config.layout_blocks {
+        let name = &block.name;
+        let problem = |field: &str, problem: String| {
+            (
+                name.clone(),
+                format!("LayoutBlock \"{}\": `{}` {}", name, field, problem),
+            )
+        };
+
+        // Fonts are just strings, so anything that isn't a style is assumed to be a real font.
+        let resolve_font = |font: &mut String| {
+            if let Some(f) = styles.fonts.get(font.as_str()) {
+                *font = f.clone();
+            }
+        };
+
+        for (_, font) in block.params.fonts_mut() {
+            resolve_font(font);
+        }
+
+        for (field, color) in block.params.colors_mut() {
+            if let Err(p) = styles.resolve_color(color) {
+                problems.push(problem(field, p));
+            }
+        }
+
+        for (field, padding) in block.params.paddings_mut() {
+            if let Err(p) = styles.resolve_padding(padding) {
+                problems.push(problem(field, p));
+            }
+        }
+
+        // Overrides can refer to styles just the same.
+        for (urgency, overrides) in block.urgency_overrides.iter_mut() {
+            let field = |name: &str| format!("urgency_overrides.{}.{}", urgency, name);
+
+            overrides.fonts.values_mut().for_each(resolve_font);
+
+            for (name, color) in &mut overrides.colors {
+                if let Err(p) = styles.resolve_color(color) {
+                    problems.push(problem(&field(name), p));
+                }
+            }
+
+            for (name, padding) in &mut overrides.paddings {
+                if let Err(p) = styles.resolve_padding(padding) {
+                    problems.push(problem(&field(name), p));
+                }
+            }
+        }
+    }
+
+    for (field, color) in [
+        ("debug_color", &mut config.debug_color),
+        ("debug_color_alt", &mut config.debug_color_alt),
+    ] {
+        if let Err(problem) = styles.resolve_color(color) {
+            problems.push(("".to_owned(), format!("`{}` {}", field, problem)));
+        }
+    }
+
+    problems
+}
+
+// Overrides have to name parameters the block actually has, or they'd never do anything.
+fn find_override_problems(blocks: &mut [LayoutBlock]) -> Vec<(String, String)> {
+    let mut problems = vec![];
+
+    for block in blocks {
+        for (urgency, overrides) in block.urgency_overrides.iter_mut() {
+            for field in overrides.unknown_fields(&mut block.params) {
+                problems.push((
+                    block.name.clone(),
+                    format!(
+                        "LayoutBlock \"{}\": `urgency_overrides.{}` overrides `{}`, but the block doesn't have that set.",
+                        block.name, urgency, field
+                    ),
+                ));
+            }
+        }
+    }
+
+    problems
+}
+
+// Best effort at finding where a block is defined in the config text, by looking for its `name`.
+// We don't get positions from serde once a value has been deserialized, so this is as good as it
+// gets.
+fn find_block_position(source: &str, name: &str) -> Option<(usize, usize)> {
+    if name.is_empty() {
+        return None;
+    }
+
+    let quoted = format!("\"{}\"", name);
+    for (idx, _) in source.match_indices(&quoted) {
+        let before = &source[..idx];
+        let is_name_field = before
+            .trim_end()
+            .strip_suffix(':')
+            .is_some_and(|b| b.trim_end().ends_with("name"));
+
+        if is_name_field {
+            let line = before.matches('\n').count() + 1;
+            let col = idx - before.rfind('\n').map_or(0, |i| i + 1) + 1;
+            return Some((line, col));
+        }
+    }
+
+    None
+}
+
 impl Default for Config {
     fn default() -> Self {
         Config::load_str(include_str!(concat!(
@@ -532,6 +1239,62 @@ impl Default for ZeroTimeoutBehavior {
     }
 }
 
+// All timeouts are in milliseconds.
+// Also used by `NotificationBlock`s, where anything that's set overrides the config's policy.
+#[derive(Debug, Deserialize, Default, Clone)]
+pub struct TimeoutPolicy {
+    // Timeouts to use instead of `timeout` when the app doesn't ask for one, for each urgency.
+    // A value of 0 means never expire.
+    pub low: Option<i32>,
+    pub normal: Option<i32>,
+    pub critical: Option<i32>,
+    // Limits on the timeouts that apps ask for.
+    pub min: Option<i32>,
+    pub max: Option<i32>,
+    // Give longer notifications more time, instead of using a fixed timeout.
+    pub reading_time: Option<ReadingTime>,
+}
+
+// Adds enough time to read the summary and body on top of a base timeout, for notifications that
+// don't ask for a timeout themselves.
+#[derive(Debug, Deserialize, Clone)]
+pub struct ReadingTime {
+    pub words_per_minute: f64,
+    // Defaults to the timeout that would have been used otherwise.
+    pub base: Option<i32>,
+    pub min: Option<i32>,
+    pub max: Option<i32>,
+}
+
+#[derive(Debug, Deserialize, Default, Clone)]
+pub struct Animations {
+    // How long each transition takes, in milliseconds.
+    pub duration: u64,
+    #[serde(default)]
+    pub easing: Easing,
+    #[serde(default)]
+    pub spawn: Transition,
+    #[serde(default)]
+    pub close: Transition,
+    // Slide notifications into their new place when the stack changes, instead of jumping.
+    #[serde(default)]
+    pub restack: bool,
+}
+
+#[derive(Debug, Deserialize, Default, Clone)]
+pub struct Transition {
+    #[serde(default)]
+    pub fade: bool,
+    // Where to slide in from / out to, relative to the notification's position.
+    pub slide: Option<Vec2>,
+}
+
+impl Transition {
+    pub fn is_enabled(&self) -> bool {
+        self.fade || self.slide.is_some()
+    }
+}
+
 #[derive(Debug, Deserialize, Clone)]
 pub enum FollowMode {
     Mouse,
@@ -544,12 +1307,146 @@ impl Default for FollowMode {
     }
 }
 
-#[derive(Debug, Deserialize, Clone)]
+// Deserializes from a number, or `Auto`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ScaleFactor {
+    // Follow the scale factor of each monitor.
+    Auto,
+    Fixed(f64),
+}
+
+impl Default for ScaleFactor {
+    fn default() -> Self {
+        Self::Fixed(1.0)
+    }
+}
+
+impl<'de> Deserialize<'de> for ScaleFactor {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct ScaleFactorVisitor;
+
+        impl<'de> de::Visitor<'de> for ScaleFactorVisitor {
+            type Value = ScaleFactor;
+
+            fn expecting(&self, f: &mut Formatter) -> fmt::Result {
+                write!(f, "a scale factor, or Auto")
+            }
+
+            fn visit_f64<E: de::Error>(self, v: f64) -> Result<Self::Value, E> {
+                Ok(ScaleFactor::Fixed(v))
+            }
+
+            fn visit_i64<E: de::Error>(self, v: i64) -> Result<Self::Value, E> {
+                Ok(ScaleFactor::Fixed(v as f64))
+            }
+
+            fn visit_u64<E: de::Error>(self, v: u64) -> Result<Self::Value, E> {
+                Ok(ScaleFactor::Fixed(v as f64))
+            }
+
+            // Ron hands bare identifiers like `Auto` over as units, without the name, so this is as
+            // picky as we can be.
+            fn visit_unit<E: de::Error>(self) -> Result<Self::Value, E> {
+                Ok(ScaleFactor::Auto)
+            }
+
+            fn visit_str<E: de::Error>(self, v: &str) -> Result<Self::Value, E> {
+                match v {
+                    "Auto" | "auto" => Ok(ScaleFactor::Auto),
+                    _ => Err(E::invalid_value(Unexpected::Str(v), &self)),
+                }
+            }
+        }
+
+        deserializer.deserialize_any(ScaleFactorVisitor)
+    }
+}
+
+#[derive(Debug, Deserialize, Clone, Default, PartialEq)]
+pub enum Backend {
+    // Wayland if we're in a Wayland session and the compositor supports the layer shell, X11
+    // (which may be XWayland) otherwise.
+    Auto,
+    // The default, since some features only work on X11.
+    #[default]
+    X11,
+    Wayland,
+}
+
+// Named values that can be used in place of the real thing, so that a theme can be changed in one
+// place, e.g. `color: "accent"`.
+// References are replaced with the values they name when the config is loaded; see `resolve_styles`.
+#[derive(Debug, Deserialize, Default)]
+pub struct Styles {
+    #[serde(default)]
+    pub colors: HashMap<String, Color>,
+    #[serde(default)]
+    pub fonts: HashMap<String, String>,
+    #[serde(default)]
+    pub paddings: HashMap<String, Padding>,
+}
+
+impl Styles {
+    // Styles from included files override ours.
+    fn merge(&mut self, other: Styles) {
+        self.colors.extend(other.colors);
+        self.fonts.extend(other.fonts);
+        self.paddings.extend(other.paddings);
+    }
+
+    fn resolve_color(&self, color: &mut Color) -> Result<(), String> {
+        let name = match &color.style {
+            Some(name) => name,
+            None => return Ok(()),
+        };
+
+        match self.colors.get(name) {
+            Some(c) if c.style.is_none() => {
+                *color = c.clone();
+                Ok(())
+            }
+            Some(_) => Err(format!(
+                "refers to the color \"{}\", which refers to another color; styles can't refer to other styles.",
+                name
+            )),
+            None => Err(format!(
+                "refers to the color \"{}\", but there is no color with that name in `styles`.",
+                name
+            )),
+        }
+    }
+
+    fn resolve_padding(&self, padding: &mut Padding) -> Result<(), String> {
+        let name = match &padding.style {
+            Some(name) => name,
+            None => return Ok(()),
+        };
+
+        match self.paddings.get(name) {
+            Some(p) if p.style.is_none() => {
+                *padding = p.clone();
+                Ok(())
+            }
+            Some(_) => Err(format!(
+                "refers to the padding \"{}\", which refers to another padding; styles can't refer to other styles.",
+                name
+            )),
+            None => Err(format!(
+                "refers to the padding \"{}\", but there is no padding with that name in `styles`.",
+                name
+            )),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
 pub struct Padding {
     pub left: f64,
     pub right: f64,
     pub top: f64,
     pub bottom: f64,
+    // The name of a padding in `styles` to use instead, until it has been resolved.
+    pub style: Option<String>,
 }
 
 #[derive(Debug, Deserialize, Clone)]
@@ -585,11 +1482,33 @@ pub struct Color {
     pub g: f64,
     pub b: f64,
     pub a: f64,
+    // The name of a color in `styles` to use instead, until it has been resolved.
+    pub style: Option<String>,
 }
 
 impl Color {
     pub fn from_rgba(r: f64, g: f64, b: f64, a: f64) -> Self {
-        Color { r, g, b, a }
+        Color {
+            r,
+            g,
+            b,
+            a,
+            style: None,
+        }
+    }
+
+    // Cairo clamps components to 0.0-1.0, so anything outside of that is usually someone using
+    // 0-255 values by mistake.
+    pub fn check(&self) -> Result<(), String> {
+        let in_range = |c: f64| (0.0..=1.0).contains(&c);
+        if [self.r, self.g, self.b, self.a].iter().all(|c| in_range(*c)) {
+            Ok(())
+        } else {
+            Err(format!(
+                "has components outside of 0.0-1.0 (r: {}, g: {}, b: {}, a: {}).  For 0-255 values, try `hex` instead.",
+                self.r, self.g, self.b, self.a
+            ))
+        }
     }
 
     pub fn from_hex(hex: &str) -> Result<Self, Error> {
@@ -645,8 +1564,16 @@ impl<'de> Deserialize<'de> for Color {
             hex: Option<String>,
         }
 
-        // Deserialize into the intermediate struct.
-        let col = Col::deserialize(deserializer)?;
+        // Deserialize into the intermediate struct, unless this is a reference to a style.
+        let col = match deserialize_or_style::<D, Col>(deserializer)? {
+            Ok(col) => col,
+            Err(style) => {
+                return Ok(Color {
+                    style: Some(style),
+                    ..Color::from_rgba(0.0, 0.0, 0.0, 0.0)
+                })
+            }
+        };
         // Check that user hasn't defined both rgba and hex.
         if col.hex.is_some() && (col.r.is_some() || col.g.is_some() || col.b.is_some() || col.a.is_some()) {
             return Err(de::Error::custom(
@@ -668,6 +1595,57 @@ impl<'de> Deserialize<'de> for Color {
     }
 }
 
+impl<'de> Deserialize<'de> for Padding {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        #[derive(Deserialize)]
+        #[serde(rename = "Padding")]
+        struct Pad {
+            left: f64,
+            right: f64,
+            top: f64,
+            bottom: f64,
+        }
+
+        match deserialize_or_style::<D, Pad>(deserializer)? {
+            Ok(p) => Ok(Padding::new(p.left, p.right, p.top, p.bottom)),
+            Err(style) => Ok(Padding {
+                style: Some(style),
+                ..Padding::new(0.0, 0.0, 0.0, 0.0)
+            }),
+        }
+    }
+}
+
+// Deserializes either a `T`, or a string naming a style to use instead (as `Err`).
+fn deserialize_or_style<'de, D, T>(deserializer: D) -> Result<Result<T, String>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: Deserialize<'de>,
+{
+    struct StyleVisitor<T>(PhantomData<T>);
+
+    impl<'de, T: Deserialize<'de>> de::Visitor<'de> for StyleVisitor<T> {
+        type Value = Result<T, String>;
+
+        fn expecting(&self, f: &mut Formatter) -> fmt::Result {
+            write!(f, "a struct, or the name of a style")
+        }
+
+        fn visit_str<E: de::Error>(self, v: &str) -> Result<Self::Value, E> {
+            Ok(Err(v.to_owned()))
+        }
+
+        fn visit_map<A: de::MapAccess<'de>>(self, map: A) -> Result<Self::Value, A::Error> {
+            T::deserialize(de::value::MapAccessDeserializer::new(map)).map(Ok)
+        }
+    }
+
+    deserializer.deserialize_any(StyleVisitor(PhantomData))
+}
+
 impl Padding {
     pub fn new(left: f64, right: f64, top: f64, bottom: f64) -> Self {
         Padding {
@@ -675,6 +1653,7 @@ impl Padding {
             right,
             top,
             bottom,
+            style: None,
         }
     }
 
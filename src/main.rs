@@ -16,6 +16,7 @@ use std::{
     fs::OpenOptions,
     io::Write,
     path::PathBuf,
+    sync::atomic::{AtomicBool, Ordering},
     time::{Duration, Instant},
 };
 
@@ -23,6 +24,7 @@ use winit::{
     event::{Event, StartCause, WindowEvent},
     event_loop::{ControlFlow, EventLoopBuilder},
     platform::run_on_demand::EventLoopExtRunOnDemand,
+    platform::wayland::EventLoopBuilderExtWayland,
     platform::x11::EventLoopBuilderExtX11,
 };
 
@@ -31,6 +33,7 @@ use cli::ShouldRun;
 use config::Config;
 use home_dir::HomeDirExt;
 use manager::NotifyWindowManager;
+use rendering::wayland::WaylandBackend;
 
 fn try_print_to_file(notification: &Notification, file: &mut File) {
     let json_string = match serde_json::to_string(&notification) {
@@ -73,6 +76,14 @@ fn open_print_file() -> Option<File> {
     None
 }
 
+// Set from the SIGHUP handler, and picked up by the main loop.  Signal handlers can't do much
+// safely, so we just flip a flag.
+static RELOAD_REQUESTED: AtomicBool = AtomicBool::new(false);
+
+extern "C" fn handle_sighup(_signal: libc::c_int) {
+    RELOAD_REQUESTED.store(true, Ordering::Relaxed);
+}
+
 fn main() {
     // If any thread panics, we want to kill the process.
     // https://stackoverflow.com/questions/35988775/how-can-i-cause-a-panic-on-a-thread-to-immediately-end-the-main-thread
@@ -91,11 +102,19 @@ fn main() {
         },
         Err(e) => {
             eprintln!("{}", e);
-            return;
+            std::process::exit(1);
         }
     };
 
-    let maybe_watcher = Config::init();
+    let mut maybe_watcher = Config::init();
+
+    // `kill -HUP` reloads the config, like most daemons.
+    unsafe {
+        libc::signal(
+            libc::SIGHUP,
+            handle_sighup as extern "C" fn(libc::c_int) as libc::sighandler_t,
+        );
+    }
     let mut maybe_print_file = open_print_file();
 
     let maybe_listener = cli::CLIListener::init().map_or_else(
@@ -109,11 +128,20 @@ fn main() {
     // Allows us to receive messages from dbus.
     let (_dbus_thread_handle, receiver) = bus::dbus::init_dbus_thread();
 
-    let mut event_loop = EventLoopBuilder::new()
-        .with_x11()
-        .build()
-        .expect("Couldn't create an X11 event loop.");
-    let mut manager = NotifyWindowManager::new(&event_loop);
+    // winit's event loop has to be on the same display server as our windows, so that monitors
+    // line up.
+    let wayland = WaylandBackend::init();
+    let mut event_loop = match wayland {
+        Some(_) => EventLoopBuilder::new()
+            .with_wayland()
+            .build()
+            .expect("Couldn't create a Wayland event loop."),
+        None => EventLoopBuilder::new()
+            .with_x11()
+            .build()
+            .expect("Couldn't create an X11 event loop."),
+    };
+    let mut manager = NotifyWindowManager::new(&event_loop, wayland);
 
     let mut poll_interval = Duration::from_millis(Config::get().poll_interval);
     let mut prev_instant = Instant::now();
@@ -137,8 +165,9 @@ fn main() {
 
                     // The polling timer for events is separate to drawing, for efficiency reasons.
                     // Read wired socket signals, for cli stuff.
+                    let mut config_reloaded = false;
                     if let Some(listener) = &maybe_listener {
-                        listener.process_messages(&mut manager, elwt);
+                        config_reloaded |= listener.process_messages(&mut manager, elwt);
                     };
 
                     // Receives `Notification`s from dbus.
@@ -156,26 +185,55 @@ fn main() {
 
                                 manager.replace_or_spawn(n, elwt);
                             }
+                            Message::DropAll => manager.drop_windows(),
+                            Message::ShowHistory(count) => manager.show_history(count as usize, elwt),
+                            Message::SetDnd(dnd) => manager.set_dnd(dnd),
+                            Message::Reload(sender) => {
+                                let result = Config::reload();
+                                config_reloaded |= result.is_ok();
+                                let _ = sender.send(result.map_err(|e| e.to_string()));
+                            }
+                            Message::ListNotifications(sender) => {
+                                let _ = sender.send(manager.list_notifications());
+                            }
                         }
                     }
 
                     // Watch config file for changes.
-                    if let Some(cw) = &maybe_watcher {
-                        // Config was changed, update some internal stuff.
-                        if cw.check_and_update_config() {
-                            poll_interval = Duration::from_millis(Config::get().poll_interval);
-                            maybe_print_file = open_print_file();
-
-                            if Config::get().notify_on_reload {
-                                manager.replace_or_spawn(
-                                    Notification::from_self(
-                                        "Wired",
-                                        "Config was reloaded.",
-                                        Timeout::Milliseconds(5000),
-                                    ),
-                                    elwt,
-                                );
-                            }
+                    if let Some(cw) = &mut maybe_watcher {
+                        config_reloaded |= cw.check_and_update_config();
+                    }
+
+                    // There's nobody to reply to for a signal, so we tell the user with a
+                    // notification instead.
+                    if RELOAD_REQUESTED.swap(false, Ordering::Relaxed) {
+                        match Config::reload() {
+                            Ok(_) => config_reloaded = true,
+                            Err(e) => manager.replace_or_spawn(
+                                Notification::from_self(
+                                    "Wired",
+                                    &maths_utility::escape_decode(format!("Couldn't reload config: {}", e)),
+                                    Timeout::Milliseconds(10000),
+                                ),
+                                elwt,
+                            ),
+                        }
+                    }
+
+                    // Config was changed, update some internal stuff.
+                    if config_reloaded {
+                        poll_interval = Duration::from_millis(Config::get().poll_interval);
+                        maybe_print_file = open_print_file();
+
+                        if Config::get().notify_on_reload {
+                            manager.replace_or_spawn(
+                                Notification::from_self(
+                                    "Wired",
+                                    "Config was reloaded.",
+                                    Timeout::Milliseconds(5000),
+                                ),
+                                elwt,
+                            );
                         }
                     }
 
@@ -17,9 +17,11 @@ use crate::{
     bus::dbus_codegen::{
         OrgFreedesktopNotificationsActionInvoked, OrgFreedesktopNotificationsNotificationClosed,
     },
-    config::Config,
-    maths_utility::{self, Rect},
+    config::{Config, ScaleFactor},
+    maths_utility::{self, Rect, Vec2},
+    rendering::blocks::{Mirror, Monitor, NotificationBlockParameters},
     rendering::layout::LayoutBlock,
+    rendering::wayland::{WaylandBackend, WaylandEvent},
     rendering::window::{NotifyWindow, UpdateModes},
 };
 
@@ -63,6 +65,8 @@ impl NotifyHistory {
 
 pub struct NotifyWindowManager {
     pub base_window: winit::window::Window,
+    // Set when we're drawing to layer surfaces rather than X11 windows, see `rendering::wayland`.
+    pub wayland: Option<WaylandBackend>,
     pub layout_windows: HashMap<String, Vec<NotifyWindow>>,
     pub history: NotifyHistory,
     pub dirty: bool,
@@ -75,10 +79,14 @@ pub struct NotifyWindowManager {
     // The idle timer last frame, from xss.
     last_idle_time: u64,
     active_monitor: Option<MonitorHandle>,
+    // The workarea of each monitor we've needed one for, as (monitor rect, workarea).  Finding
+    // them takes a round trip to the X server per client, so they're only refreshed in the slow
+    // update.
+    workareas: Vec<(Rect, Option<Rect>)>,
 }
 
 impl NotifyWindowManager {
-    pub fn new(el: &EventLoopWindowTarget<()>) -> Self {
+    pub fn new(el: &EventLoopWindowTarget<()>, wayland: Option<WaylandBackend>) -> Self {
         // Create a map for each layout type, which allows us to easily keep track of different
         // layouts later.
         let mut layout_windows = HashMap::new();
@@ -95,6 +103,7 @@ impl NotifyWindowManager {
 
         Self {
             base_window,
+            wayland,
             layout_windows,
             history: NotifyHistory::new(Config::get().history_length),
             dirty: false,
@@ -103,6 +112,7 @@ impl NotifyWindowManager {
             slow_update_timer: 0.0,
             last_idle_time: 0,
             active_monitor,
+            workareas: vec![],
 
             should_exit: false,
         }
@@ -116,19 +126,38 @@ impl NotifyWindowManager {
             if !notification_meets_layout_criteria(layout, &notification) {
                 continue;
             } else {
-                let window = NotifyWindow::new(el, notification.clone(), layout.clone(), self);
-
-                // Find this notification's layout and push the window there.
-                let windows = self
-                    .layout_windows
-                    .get_mut(&layout.name)
-                    .expect("Somehow created a new layout.");
-                windows.push(window);
-
-                // If we've exceeded max notifications, then mark the top-most one for destroy.
-                let cfg = Config::get();
-                if cfg.max_notifications > 0 && windows.len() > cfg.max_notifications {
-                    windows.first_mut().unwrap().marked_for_destroy = true;
+                // Mirrored layouts get one window per monitor.
+                let layout_params = layout.as_notification_block();
+                for mirror in 0..mirror_count(&self.base_window, layout_params) {
+                    let monitor =
+                        find_target_monitor(&self.base_window, &self.active_monitor, layout_params, mirror);
+                    let scale = scale_factor(monitor.as_ref());
+                    let mut window = NotifyWindow::new(
+                        el,
+                        notification.clone(),
+                        layout.clone(),
+                        self,
+                        monitor.as_ref(),
+                        scale,
+                    );
+                    window.mirror = mirror;
+
+                    // Find this notification's layout and push the window there.
+                    let windows = self
+                        .layout_windows
+                        .get_mut(&layout.name)
+                        .expect("Somehow created a new layout.");
+                    windows.push(window);
+
+                    // If we've exceeded max notifications, then mark the top-most one for destroy.
+                    // Each monitor's copies are counted separately.
+                    let showing: Vec<(usize, bool)> = windows
+                        .iter()
+                        .map(|w| (w.mirror, !w.marked_for_destroy && !w.is_closing()))
+                        .collect();
+                    if let Some(i) = window_to_evict(&showing, mirror, Config::get().max_notifications) {
+                        windows[i].marked_for_destroy = true;
+                    }
                 }
 
                 // Outer state is now out of sync with internal state because we have an invisible notification.
@@ -137,6 +166,15 @@ impl NotifyWindowManager {
         }
     }
 
+    // Bring back the `count` most recent notifications from history.
+    pub fn show_history(&mut self, count: usize, el: &EventLoopWindowTarget<()>) {
+        for _ in 0..count {
+            if let Some(n) = self.history.pop_back() {
+                self.new_notification(n, el);
+            }
+        }
+    }
+
     pub fn replace_or_spawn(&mut self, notification: Notification, el: &EventLoopWindowTarget<()>) {
         let cfg = Config::get();
         if cfg.debug {
@@ -152,8 +190,15 @@ impl NotifyWindowManager {
         if let Some(layout) = find_matching_layout(&notification) {
             // Find any windows that have the same id, or the same app name and tag.
             // If one exists then we should replace that (if replacing is enabled).
+            // Windows on their way out are left alone, otherwise the replacement would be dropped
+            // along with them.
             let mut maybe_windows = vec![];
-            for w in self.layout_windows.values_mut().flatten() {
+            let live_windows = self
+                .layout_windows
+                .values_mut()
+                .flatten()
+                .filter(|w| !w.marked_for_destroy && !w.is_closing());
+            for w in live_windows {
                 let id_matches = (w.notification.id == notification.id) && cfg.replacing_enabled;
                 let tag_matches = w.notification.app_name == notification.app_name
                     && w.notification.tag.is_some()
@@ -176,6 +221,7 @@ impl NotifyWindowManager {
 
     pub fn update(&mut self, delta_time: Duration) {
         let cfg = Config::get();
+        self.process_wayland_events();
 
         // Idle threshold granularity is 1s,
         // but I want to update active monitor faster than that.
@@ -190,7 +236,17 @@ impl NotifyWindowManager {
                 self.dirty = true;
             }
 
-            if let Some(threshold) = cfg.idle_threshold {
+            // Panels and docks can come and go.
+            for (monitor_rect, workarea) in &mut self.workareas {
+                let current = maths_utility::get_workarea(&self.base_window, monitor_rect);
+                if current != *workarea {
+                    *workarea = current;
+                    self.dirty = true;
+                }
+            }
+
+            // There's no screensaver extension to ask on Wayland.
+            if let (Some(threshold), None) = (cfg.idle_threshold, &self.wayland) {
                 match maths_utility::query_screensaver_info(&self.base_window) {
                     Ok(info) => {
                         // 1s to be considered idle.
@@ -229,13 +285,46 @@ impl NotifyWindowManager {
         }
 
         if self.dirty {
+            // Mirrored copies go together, so one copy's fuse running out closes all of them.
+            for windows in self.layout_windows.values_mut() {
+                let expired: Vec<u32> = windows
+                    .iter()
+                    .filter(|w| w.marked_for_destroy && !w.is_closing())
+                    .map(|w| w.notification.id)
+                    .collect();
+                for window in windows
+                    .iter_mut()
+                    .filter(|w| expired.contains(&w.notification.id))
+                {
+                    window.marked_for_destroy = true;
+                }
+            }
+
             self.update_positions();
             // Finally drop windows.
             for windows in self.layout_windows.values_mut() {
-                // Send signal for notifications that are going to be closed, then drop them.
-                for window in windows.iter().filter(|w| w.marked_for_destroy) {
+                // Windows with a close animation stick around until it's done.
+                for window in windows.iter_mut().filter(|w| w.marked_for_destroy) {
+                    window.begin_close();
+                }
+
+                let mut dropped: Vec<Notification> = vec![];
+                windows.retain(|w| {
+                    let keep = !w.marked_for_destroy || w.is_closing();
+                    if !keep && !dropped.iter().any(|n| n.id == w.notification.id) {
+                        dropped.push(w.notification.clone());
+                    }
+                    keep
+                });
+
+                // Send signal for notifications that have been closed.  Mirrored copies can finish
+                // closing at different times, so wait until the last one has gone.
+                for notification in dropped
+                    .into_iter()
+                    .filter(|n| !windows.iter().any(|w| w.notification.id == n.id))
+                {
                     let message = OrgFreedesktopNotificationsNotificationClosed {
-                        id: window.notification.id,
+                        id: notification.id,
                         reason: 4, // TODO: get real reason. -- 1 expired, 2 dismissed by user, 3 `CloseNotification`, 4 undefined.
                     };
                     let path = Path::new(bus::dbus::PATH).expect("Failed to create DBus path.");
@@ -250,17 +339,49 @@ impl NotifyWindowManager {
                     if self.history.len() + 1 > Config::get().history_length {
                         let _ = self.history.pop_front();
                     }
-                    self.history.push(window.notification.clone());
+                    self.history.push(notification);
                 }
+            }
+        }
+
+        if let Some(wayland) = &self.wayland {
+            wayland.flush();
+        }
+    }
+
+    // Layer surfaces get their events through our own Wayland connection rather than winit's
+    // event loop, so we pass them along here.
+    fn process_wayland_events(&mut self) {
+        let events = match &mut self.wayland {
+            Some(wayland) => wayland.dispatch(),
+            None => return,
+        };
 
-                windows.retain(|w| !w.marked_for_destroy);
+        for event in events {
+            match event {
+                WaylandEvent::Configured(window_id) => {
+                    if let Some(window) = self.find_window_mut(window_id) {
+                        window.configured();
+                    }
+                }
+                WaylandEvent::Closed(window_id) => {
+                    self.drop_window_id(window_id);
+                }
+                WaylandEvent::Input(window_id, mut event) => {
+                    // Positions come in logical pixels, but we expect physical ones like on X11.
+                    if let WindowEvent::CursorMoved { position, .. } = &mut event {
+                        let scale = self.find_window(window_id).map_or(1.0, |w| w.scale);
+                        *position = PhysicalPosition::new(position.x * scale, position.y * scale);
+                    }
+                    self.process_event(window_id, event);
+                }
             }
         }
     }
 
     fn update_positions(&mut self) {
         let cfg = Config::get();
-        for (layout_name, windows) in &self.layout_windows {
+        for (layout_name, windows) in &mut self.layout_windows {
             // If there are no windows for this layout, leave it alone.
             if windows.is_empty() {
                 continue;
@@ -274,68 +395,73 @@ impl NotifyWindowManager {
                 .expect("Failed to find matching layout.");
             let layout_params = layout.as_notification_block();
 
-            let monitor = {
-                let mut maybe_monitor;
-                // Use cursor focus.
-                if layout_params.monitor < 0 {
-                    maybe_monitor = self.active_monitor.clone();
-                } else {
-                    maybe_monitor = self
-                        .base_window
-                        .available_monitors()
-                        .nth(layout_params.monitor as usize)
-                }
-
-                // Fallback, try to use primary monitor.
-                if maybe_monitor.is_none() {
-                    maybe_monitor = self.base_window.primary_monitor();
-                }
-
+            for mirror in 0..mirror_count(&self.base_window, layout_params) {
                 // If we can't find a monitor, it's basically over.
                 // But we don't have to crash.  Maybe we'll find a monitor next
                 // time (if it was unplugged or something).
-                match maybe_monitor {
+                let maybe_monitor =
+                    find_target_monitor(&self.base_window, &self.active_monitor, layout_params, mirror);
+                let monitor = match maybe_monitor {
                     Some(m) => m,
                     None => continue,
-                }
-            };
-
-            let (pos, size) = (monitor.position(), monitor.size());
-            let monitor_rect = Rect::new(pos.x.into(), pos.y.into(), size.width.into(), size.height.into());
-            let mut prev_rect = monitor_rect;
-
-            let mut real_idx = 0;
-            for window in windows {
-                // Windows which are marked for destroy should be overlapped so that destroying them
-                // will be less noticeable.
-                if window.marked_for_destroy {
-                    continue;
-                }
-
-                let mut window_rect = window.get_inner_rect();
+                };
 
-                // For the first notification, we attach to the monitor.
-                // For the second and more notifications, we attach to the previous
-                // notification.
-                let pos = if real_idx == 0 {
-                    LayoutBlock::find_anchor_pos(&layout.hook, &layout.offset, &prev_rect, &window_rect)
+                let (pos, size) = (monitor.position(), monitor.size());
+                let monitor_rect =
+                    Rect::new(pos.x.into(), pos.y.into(), size.width.into(), size.height.into());
+                // Panels and docks reserve space at the edges of the monitor, which we can keep out of.
+                let monitor_rect = if layout_params.use_workarea {
+                    cached_workarea(&mut self.workareas, &self.base_window, &monitor_rect)
+                        .unwrap_or(monitor_rect)
                 } else {
-                    LayoutBlock::find_anchor_pos(
-                        &layout_params.notification_hook,
-                        &layout_params.gap,
-                        &prev_rect,
-                        &window_rect,
-                    )
+                    monitor_rect
                 };
+                let mut prev_rect = monitor_rect;
+                let scale = scale_factor(Some(&monitor));
+
+                // `offset` and `gap` are in logical pixels, like the rest of the layout.
+                let scaled = |v: &Vec2| Vec2::new(v.x * scale, v.y * scale);
+                let (offset, gap) = (scaled(&layout.offset), scaled(&layout_params.gap));
+
+                let mut real_idx = 0;
+                for window in windows.iter_mut().filter(|w| w.mirror == mirror) {
+                    // Windows which are marked for destroy should be overlapped so that destroying them
+                    // will be less noticeable.
+                    if window.marked_for_destroy {
+                        continue;
+                    }
+
+                    // The window may have been moved to a monitor with a different scale.
+                    if (window.scale - scale).abs() > f64::EPSILON {
+                        window.set_scale(scale);
+                    }
+
+                    let mut window_rect = window.get_inner_rect();
+
+                    // For the first notification, we attach to the monitor.
+                    // For the second and more notifications, we attach to the previous
+                    // notification.
+                    let pos = if real_idx == 0 {
+                        LayoutBlock::find_anchor_pos(&layout.hook, &offset, &prev_rect, &window_rect)
+                    } else {
+                        LayoutBlock::find_anchor_pos(
+                            &layout_params.notification_hook,
+                            &gap,
+                            &prev_rect,
+                            &window_rect,
+                        )
+                    };
 
-                // Note: `set_position` doesn't happen instantly.  If we read
-                // `get_rect()`s position straight after this call it probably won't be correct,
-                // which is why we `set_xy` manually after.
-                window.set_position(pos.x, pos.y);
-                window_rect.set_xy(pos.x, pos.y);
-                prev_rect = window_rect;
+                    // Note: `set_position` doesn't happen instantly.  If we read
+                    // `get_rect()`s position straight after this call it probably won't be correct,
+                    // which is why we `set_xy` manually after.
+                    // We also might be animating there rather than jumping.
+                    window.move_to(pos.clone());
+                    window_rect.set_xy(pos.x, pos.y);
+                    prev_rect = window_rect;
 
-                real_idx += 1;
+                    real_idx += 1;
+                }
             }
         }
 
@@ -353,6 +479,12 @@ impl NotifyWindowManager {
                 if let Some(window) = self.find_window_mut(window_id) {
                     window.process_mouse_move(position);
                 }
+                // Hovering any copy holds the notification, so copies don't time out separately.
+                if Config::get().pause_on_hover {
+                    for window in self.find_window_copies_mut(window_id) {
+                        window.set_hovered(true);
+                    }
+                }
             }
 
             // If we don't notify when the cursor left, then we have issues moving the cursor off
@@ -363,6 +495,9 @@ impl NotifyWindowManager {
                 if let Some(window) = self.find_window_mut(window_id) {
                     window.process_mouse_move(PhysicalPosition::new(-1.0, -1.0));
                 }
+                for window in self.find_window_copies_mut(window_id) {
+                    window.set_hovered(false);
+                }
             }
 
             WindowEvent::MouseInput {
@@ -398,8 +533,8 @@ impl NotifyWindowManager {
         } else if pressed == config.shortcuts.notification_closeall {
             self.drop_windows();
         } else if pressed == config.shortcuts.notification_pause {
-            if let Some(window) = self.find_window_mut(window_id) {
-                window.update_mode.toggle(UpdateModes::FUSE);
+            for window in self.find_window_copies_mut(window_id) {
+                window.toggle_pause();
             }
         } else {
             // Request the window to be dropped if we got a close action.
@@ -485,7 +620,7 @@ impl NotifyWindowManager {
     // Find window across all monitors based on an id, which we receive from winit events.
     pub fn _find_window_idx(&self, window_id: WindowId) -> Option<(&str, usize)> {
         for (layout_name, windows) in &self.layout_windows {
-            let found = windows.iter().position(|w| w.winit.id() == window_id);
+            let found = windows.iter().position(|w| w.id() == window_id);
             if let Some(idx) = found {
                 return Some((layout_name, idx));
             }
@@ -502,24 +637,34 @@ impl NotifyWindowManager {
         self.layout_windows
             .values()
             .flatten()
-            .find(|w| w.winit.id() == window_id)
+            .find(|w| w.id() == window_id)
     }
 
     pub fn find_window_mut(&mut self, window_id: WindowId) -> Option<&mut NotifyWindow> {
         self.layout_windows
             .values_mut()
             .flatten()
-            .find(|w| w.winit.id() == window_id)
+            .find(|w| w.id() == window_id)
     }
 
     pub fn find_window_ordered(&self, num: usize) -> Option<WindowId> {
-        let mut windows: Vec<&NotifyWindow> = self.layout_windows.values().flatten().collect();
+        let mut windows = self.unmirrored_windows();
 
         // `sort_unstable` is faster, but windows with the exact same creation timestamp may by
         // shifted in ordering, which is undersireable.  DateTime is probably precise enough to get
         // away with this, but frankly I just don't want to worry about it.
         windows.sort_by(|a, b| a.creation_timestamp.partial_cmp(&b.creation_timestamp).unwrap());
-        windows.get(num).map(|w| w.winit.id())
+        windows.get(num).map(|w| w.id())
+    }
+
+    // Every window, leaving out mirrored copies so that each notification is only counted once
+    // per layout.
+    pub fn unmirrored_windows(&self) -> Vec<&NotifyWindow> {
+        self.layout_windows
+            .values()
+            .flatten()
+            .filter(|w| w.mirror == 0)
+            .collect()
     }
 
     pub fn find_window_nid(&self, notification_id: u32) -> Option<WindowId> {
@@ -527,18 +672,38 @@ impl NotifyWindowManager {
             .values()
             .flatten()
             .find(|w| w.notification.id == notification_id)
-            .map(|w| w.winit.id())
+            .map(|w| w.id())
     }
 
-    // Drop a window.  Return true if we found the window and told it to drop, false otherwise.
+    // Find a window and any mirrored copies of it (see `NotificationBlockParameters::mirror`).
+    // Empty if we don't know about the window.
+    pub fn find_window_copies_mut(&mut self, window_id: WindowId) -> Vec<&mut NotifyWindow> {
+        for windows in self.layout_windows.values_mut() {
+            let found = windows
+                .iter()
+                .find(|w| w.id() == window_id)
+                .map(|w| w.notification.id);
+            if let Some(id) = found {
+                return windows.iter_mut().filter(|w| w.notification.id == id).collect();
+            }
+        }
+
+        vec![]
+    }
+
+    // Drop a window, and its copies on other monitors.  Return true if we found the window and
+    // told it to drop, false otherwise.
     pub fn drop_window_id(&mut self, window_id: WindowId) -> bool {
-        if let Some(window) = self.find_window_mut(window_id) {
-            window.marked_for_destroy = true;
-            self.dirty = true;
-            return true;
+        let copies = self.find_window_copies_mut(window_id);
+        if copies.is_empty() {
+            return false;
         }
 
-        false
+        for window in copies {
+            window.marked_for_destroy = true;
+        }
+        self.dirty = true;
+        true
     }
 
     // @TODO: how about a shortcut for dropping all windows on one monitor?  Support multi-monitor
@@ -571,19 +736,21 @@ impl NotifyWindowManager {
     }
 
     pub fn drop_notification(&mut self, id: u32) -> bool {
-        // This should be Some, otherwise we were given a bad id.
-        let maybe_window = self
+        // This should find something, otherwise we were given a bad id.
+        // Mirrored layouts can have more than one window per notification.
+        let mut found = false;
+        for window in self
             .layout_windows
             .values_mut()
             .flatten()
-            .find(|w| w.notification.id == id);
-        if let Some(window) = maybe_window {
+            .filter(|w| w.notification.id == id)
+        {
             window.marked_for_destroy = true;
-            self.dirty = true;
-            return true;
+            found = true;
         }
 
-        false
+        self.dirty |= found;
+        found
     }
 
     pub fn has_windows(&self) -> bool {
@@ -600,7 +767,113 @@ impl NotifyWindowManager {
     }
 
     pub fn set_dnd(&mut self, val: bool) {
-        self.dnd = val;
+        if self.dnd != val {
+            self.dnd = val;
+            bus::dbus::publish_dnd(val);
+        }
+    }
+
+    // A summary of every notification currently on screen, oldest first.
+    pub fn list_notifications(&self) -> Vec<(u32, String, String, String)> {
+        let mut windows = self.unmirrored_windows();
+        windows.sort_by(|a, b| a.creation_timestamp.partial_cmp(&b.creation_timestamp).unwrap());
+        windows
+            .iter()
+            .map(|w| {
+                let n = &w.notification;
+                (n.id, n.app_name.clone(), n.summary.clone(), n.body.clone())
+            })
+            .collect()
+    }
+}
+
+// Which window to get rid of, if there are more than `max` showing on `mirror`'s monitor.  Windows
+// are given as `(mirror, showing)`, oldest first.  Windows that are already on their way out don't
+// count, otherwise we'd keep picking the same one while its close animation plays.
+fn window_to_evict(windows: &[(usize, bool)], mirror: usize, max: usize) -> Option<usize> {
+    let mut showing = windows
+        .iter()
+        .enumerate()
+        .filter(|(_, &(m, showing))| m == mirror && showing)
+        .map(|(i, _)| i);
+
+    if max > 0 && showing.clone().count() > max {
+        showing.next()
+    } else {
+        None
+    }
+}
+
+// How many windows a layout spawns for each notification; one per monitor it's mirrored to.
+fn mirror_count(base_window: &winit::window::Window, params: &NotificationBlockParameters) -> usize {
+    match &params.mirror {
+        Some(Mirror::All) => base_window.available_monitors().count().max(1),
+        Some(Mirror::Monitors(monitors)) => monitors.len().max(1),
+        None => 1,
+    }
+}
+
+// Look up a monitor's workarea in `cache`, only asking the X server if it isn't there yet.
+fn cached_workarea(
+    cache: &mut Vec<(Rect, Option<Rect>)>,
+    base_window: &winit::window::Window,
+    monitor_rect: &Rect,
+) -> Option<Rect> {
+    if let Some((_, workarea)) = cache.iter().find(|(rect, _)| rect == monitor_rect) {
+        return workarea.clone();
+    }
+
+    let workarea = maths_utility::get_workarea(base_window, monitor_rect);
+    cache.push((monitor_rect.clone(), workarea.clone()));
+    workarea
+}
+
+// The monitor that one copy of a layout's notifications should be shown on.
+fn find_target_monitor(
+    base_window: &winit::window::Window,
+    active_monitor: &Option<MonitorHandle>,
+    params: &NotificationBlockParameters,
+    mirror: usize,
+) -> Option<MonitorHandle> {
+    match &params.mirror {
+        Some(Mirror::All) => base_window.available_monitors().nth(mirror),
+        // Mirrored copies don't fall back to anything, otherwise they'd end up on top of each other.
+        Some(Mirror::Monitors(monitors)) => find_monitor(base_window, active_monitor, monitors.get(mirror)?),
+        // Take the first one that's connected.
+        // Fallback, try to use primary monitor.
+        None => params
+            .monitor
+            .iter()
+            .find_map(|m| find_monitor(base_window, active_monitor, m))
+            .or_else(|| base_window.primary_monitor())
+            // Wayland has no primary monitor.
+            .or_else(|| base_window.available_monitors().next()),
+    }
+}
+
+// Find a connected monitor.  Output names are looked up each time, since monitor order can change
+// when they're plugged in or out.
+fn find_monitor(
+    base_window: &winit::window::Window,
+    active_monitor: &Option<MonitorHandle>,
+    monitor: &Monitor,
+) -> Option<MonitorHandle> {
+    match monitor {
+        Monitor::Index(idx) => base_window.available_monitors().nth(*idx),
+        Monitor::Name(name) => base_window
+            .available_monitors()
+            .find(|m| m.name().as_deref() == Some(name.as_str())),
+        Monitor::Primary => base_window.primary_monitor(),
+        // Use cursor focus.
+        Monitor::Active => active_monitor.clone(),
+    }
+}
+
+// How many physical pixels each logical pixel of a layout takes up on `monitor`.
+fn scale_factor(monitor: Option<&MonitorHandle>) -> f64 {
+    match Config::get().scale_factor {
+        ScaleFactor::Auto => monitor.map_or(1.0, |m| m.scale_factor()),
+        ScaleFactor::Fixed(scale) => scale,
     }
 }
 
@@ -669,3 +942,60 @@ pub fn notification_meets_any_criteria(notification: &Notification) -> bool {
     false
 }
 */
+
+#[cfg(test)]
+mod tests {
+    use super::window_to_evict;
+
+    // Spawn notifications one after another, evicting as `new_notification` does.  Evicted
+    // windows stay around (closing) for the rest of the test.
+    fn spawn(windows: &mut Vec<(usize, bool)>, mirror: usize, max: usize) {
+        windows.push((mirror, true));
+        if let Some(i) = window_to_evict(windows, mirror, max) {
+            windows[i].1 = false;
+        }
+    }
+
+    fn showing(windows: &[(usize, bool)], mirror: usize) -> usize {
+        windows.iter().filter(|&&(m, s)| m == mirror && s).count()
+    }
+
+    #[test]
+    fn evicts_past_closing_windows() {
+        let max = 3;
+        let mut windows = vec![];
+        for _ in 0..max + 2 {
+            spawn(&mut windows, 0, max);
+        }
+
+        assert_eq!(showing(&windows, 0), max);
+        // The two oldest are the ones on their way out.
+        assert_eq!(
+            windows,
+            vec![(0, false), (0, false), (0, true), (0, true), (0, true)]
+        );
+    }
+
+    #[test]
+    fn counts_mirrors_separately() {
+        let max = 2;
+        let mut windows = vec![];
+        for _ in 0..max + 2 {
+            spawn(&mut windows, 0, max);
+            spawn(&mut windows, 1, max);
+        }
+
+        assert_eq!(showing(&windows, 0), max);
+        assert_eq!(showing(&windows, 1), max);
+    }
+
+    #[test]
+    fn no_limit() {
+        let mut windows = vec![];
+        for _ in 0..10 {
+            spawn(&mut windows, 0, 0);
+        }
+
+        assert_eq!(showing(&windows, 0), 10);
+    }
+}
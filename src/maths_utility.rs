@@ -1,4 +1,5 @@
 #![allow(dead_code)]
+use std::os::raw::{c_int, c_ulong};
 use std::process::{Command, Stdio};
 
 use crate::bus::dbus::Notification;
@@ -16,7 +17,129 @@ pub struct MinMax {
     pub max: i32,
 }
 
-#[derive(Default, Debug, Clone, Deserialize)]
+// A radius for each corner of a rectangle.
+// Deserializes from a single radius for every corner, or from
+// `(top_left: 3.0, top_right: 0.0, bottom_right: 0.0, bottom_left: 3.0)`, where any that are left out
+// are square.
+#[derive(Default, Debug, Clone, PartialEq)]
+pub struct Corners {
+    pub top_left: f64,
+    pub top_right: f64,
+    pub bottom_right: f64,
+    pub bottom_left: f64,
+}
+
+impl Corners {
+    pub fn all(radius: f64) -> Self {
+        Self { top_left: radius, top_right: radius, bottom_right: radius, bottom_left: radius }
+    }
+
+    pub fn map(&self, f: impl Fn(f64) -> f64) -> Self {
+        Self {
+            top_left: f(self.top_left),
+            top_right: f(self.top_right),
+            bottom_right: f(self.bottom_right),
+            bottom_left: f(self.bottom_left),
+        }
+    }
+
+    // Shrink the radii to fit inside a `width` x `height` rectangle, so neighbouring corners
+    // can't overlap.
+    pub fn clamped(&self, width: f64, height: f64) -> Self {
+        let max = (width.min(height) / 2.0).max(0.0);
+        self.map(|r| r.clamp(0.0, max))
+    }
+
+    // The radii for the inside edge of a border with `sides` widths, so it follows the outside
+    // edge.
+    pub fn inset(&self, sides: &Sides) -> Self {
+        Self {
+            top_left: (self.top_left - sides.top.max(sides.left)).max(0.0),
+            top_right: (self.top_right - sides.top.max(sides.right)).max(0.0),
+            bottom_right: (self.bottom_right - sides.bottom.max(sides.right)).max(0.0),
+            bottom_left: (self.bottom_left - sides.bottom.max(sides.left)).max(0.0),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for Corners {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum Repr {
+            All(f64),
+            Each {
+                #[serde(default)]
+                top_left: f64,
+                #[serde(default)]
+                top_right: f64,
+                #[serde(default)]
+                bottom_right: f64,
+                #[serde(default)]
+                bottom_left: f64,
+            },
+        }
+
+        Ok(match Repr::deserialize(deserializer)? {
+            Repr::All(radius) => Corners::all(radius),
+            Repr::Each { top_left, top_right, bottom_right, bottom_left } => {
+                Corners { top_left, top_right, bottom_right, bottom_left }
+            }
+        })
+    }
+}
+
+// A width for each side of a rectangle, e.g. for borders.
+// Deserializes from a single width for every side, or from
+// `(left: 0.0, right: 3.0, top: 3.0, bottom: 3.0)`, where any that are left out are 0.
+#[derive(Default, Debug, Clone, PartialEq)]
+pub struct Sides {
+    pub left: f64,
+    pub right: f64,
+    pub top: f64,
+    pub bottom: f64,
+}
+
+impl Sides {
+    pub fn all(width: f64) -> Self {
+        Self { left: width, right: width, top: width, bottom: width }
+    }
+
+    pub fn width(&self) -> f64 {
+        self.left + self.right
+    }
+
+    pub fn height(&self) -> f64 {
+        self.top + self.bottom
+    }
+}
+
+impl<'de> Deserialize<'de> for Sides {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum Repr {
+            All(f64),
+            Each {
+                #[serde(default)]
+                left: f64,
+                #[serde(default)]
+                right: f64,
+                #[serde(default)]
+                top: f64,
+                #[serde(default)]
+                bottom: f64,
+            },
+        }
+
+        Ok(match Repr::deserialize(deserializer)? {
+            Repr::All(width) => Sides::all(width),
+            Repr::Each { left, right, top, bottom } => Sides { left, right, top, bottom },
+        })
+    }
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Deserialize)]
 pub struct Vec2 {
     pub x: f64,
     pub y: f64,
@@ -28,7 +151,7 @@ impl Vec2 {
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct Rect {
     x: f64,
     y: f64,
@@ -293,6 +416,82 @@ pub fn distance(x: f64, y: f64) -> f64 {
     }
 }
 
+// https://easings.net
+#[derive(Debug, Deserialize, Clone, Copy, Default)]
+pub enum Easing {
+    Linear,
+    EaseInQuad,
+    EaseOutQuad,
+    EaseInOutQuad,
+    EaseInCubic,
+    #[default]
+    EaseOutCubic,
+    EaseInOutCubic,
+}
+
+impl Easing {
+    // Maps progress `t` (0.0 to 1.0) onto the curve.
+    pub fn apply(&self, t: f64) -> f64 {
+        let t = clamp(t, 0.0, 1.0);
+        match self {
+            Easing::Linear => t,
+            Easing::EaseInQuad => t * t,
+            Easing::EaseOutQuad => 1.0 - (1.0 - t) * (1.0 - t),
+            Easing::EaseInOutQuad => {
+                if t < 0.5 {
+                    2.0 * t * t
+                } else {
+                    1.0 - (-2.0 * t + 2.0).powi(2) / 2.0
+                }
+            }
+            Easing::EaseInCubic => t * t * t,
+            Easing::EaseOutCubic => 1.0 - (1.0 - t).powi(3),
+            Easing::EaseInOutCubic => {
+                if t < 0.5 {
+                    4.0 * t * t * t
+                } else {
+                    1.0 - (-2.0 * t + 2.0).powi(3) / 2.0
+                }
+            }
+        }
+    }
+}
+
+// Cover a rounded rectangle with plain ones, a row of pixels at a time through the corners, for
+// things that only understand rectangles (like X11 window shapes).  Everything is rounded to whole
+// pixels.
+pub fn rounded_rectangle_spans(rect: &Rect, corners: &Corners) -> Vec<Rect> {
+    let (x, y) = (rect.left().round(), rect.top().round());
+    let (width, height) = (rect.right().round() - x, rect.bottom().round() - y);
+    let corners = corners.clamped(width, height);
+    // How far in from the side a corner of radius `r` is, `d` pixels from its end.
+    let indent = |r: f64, d: f64| if d < r { r - (r * r - (r - d).powi(2)).sqrt() } else { 0.0 };
+
+    let mut spans: Vec<Rect> = vec![];
+    for row in 0..height.max(0.0) as usize {
+        // Measured from the middle of the row.
+        let (from_top, from_bottom) = (row as f64 + 0.5, height - row as f64 - 0.5);
+        let left = indent(corners.top_left, from_top).max(indent(corners.bottom_left, from_bottom));
+        let right = indent(corners.top_right, from_top).max(indent(corners.bottom_right, from_bottom));
+        let (left, right) = (left.round(), right.round());
+        if left + right >= width {
+            continue;
+        }
+
+        // Rows that match the one above are merged, so the straight part in the middle is just one
+        // rect.
+        let span = Rect::new(x + left, y + row as f64, width - left - right, 1.0);
+        match spans.last_mut() {
+            Some(last) if (last.x(), last.width(), last.bottom()) == (span.x(), span.width(), span.y()) => {
+                last.set_height(last.height() + 1.0)
+            }
+            _ => spans.push(span),
+        }
+    }
+
+    spans
+}
+
 // http://cairographics.org/samples/rounded_rectangle/
 pub fn cairo_path_rounded_rectangle(
     ctx: &cairo::Context,
@@ -300,39 +499,36 @@ pub fn cairo_path_rounded_rectangle(
     y: f64,
     width: f64,
     height: f64,
-    corner_radius: f64,
+    corners: &Corners,
 ) -> Result<(), cairo::Error> {
     ctx.save()?;
 
-    // Aspect ratio.
-    let aspect = 1.0;
-    let radius = corner_radius / aspect;
-
     let degrees = std::f64::consts::PI / 180.0;
+    let Corners { top_left, top_right, bottom_right, bottom_left } = *corners;
 
     ctx.new_sub_path();
     ctx.arc(
-        x + width - radius,
-        y + radius,
-        radius,
+        x + width - top_right,
+        y + top_right,
+        top_right,
         -90.0 * degrees,
         0.0 * degrees,
     );
     ctx.arc(
-        x + width - radius,
-        y + height - radius,
-        radius,
+        x + width - bottom_right,
+        y + height - bottom_right,
+        bottom_right,
         0.0 * degrees,
         90.0 * degrees,
     );
     ctx.arc(
-        x + radius,
-        y + height - radius,
-        radius,
+        x + bottom_left,
+        y + height - bottom_left,
+        bottom_left,
         90.0 * degrees,
         180.0 * degrees,
     );
-    ctx.arc(x + radius, y + radius, radius, 180.0 * degrees, 270.0 * degrees);
+    ctx.arc(x + top_left, y + top_left, top_left, 180.0 * degrees, 270.0 * degrees);
     ctx.close_path();
 
     ctx.restore()?;
@@ -346,36 +542,33 @@ pub fn cairo_path_rounded_rectangle_inverse(
     y: f64,
     width: f64,
     height: f64,
-    corner_radius: f64,
+    corners: &Corners,
 ) -> Result<(), cairo::Error> {
     ctx.save()?;
 
-    // Aspect ratio.
-    let aspect = 1.0;
-    let radius = corner_radius / aspect;
-
     let degrees = std::f64::consts::PI / 180.0;
+    let Corners { top_left, top_right, bottom_right, bottom_left } = *corners;
 
     ctx.new_sub_path();
-    ctx.arc_negative(x + radius, y + radius, radius, 270.0 * degrees, 180.0 * degrees);
+    ctx.arc_negative(x + top_left, y + top_left, top_left, 270.0 * degrees, 180.0 * degrees);
     ctx.arc_negative(
-        x + radius,
-        y + height - radius,
-        radius,
+        x + bottom_left,
+        y + height - bottom_left,
+        bottom_left,
         180.0 * degrees,
         90.0 * degrees,
     );
     ctx.arc_negative(
-        x + width - radius,
-        y + height - radius,
-        radius,
+        x + width - bottom_right,
+        y + height - bottom_right,
+        bottom_right,
         90.0 * degrees,
         0.0 * degrees,
     );
     ctx.arc_negative(
-        x + width - radius,
-        y + radius,
-        radius,
+        x + width - top_right,
+        y + top_right,
+        top_right,
         0.0 * degrees,
         -90.0 * degrees,
     );
@@ -396,8 +589,8 @@ pub fn cairo_rounded_bordered_rectangle(
     y: f64,
     width: f64,
     height: f64,
-    corner_radius: f64,
-    thickness: f64,
+    corners: &Corners,
+    sides: &Sides,
     fg_color: &Color,
     bg_color: &Color,
 ) -> Result<(), cairo::Error> {
@@ -406,17 +599,17 @@ pub fn cairo_rounded_bordered_rectangle(
     // push_group() calls ctx.save().
     ctx.push_group();
     ctx.set_operator(cairo::Operator::Source);
-    cairo_path_rounded_rectangle(ctx, x, y, width, height, corner_radius)?;
+    cairo_path_rounded_rectangle(ctx, x, y, width, height, corners)?;
     ctx.set_source_rgba(fg_color.r, fg_color.g, fg_color.b, fg_color.a);
     ctx.fill()?;
 
     cairo_path_rounded_rectangle(
         ctx,
-        x + thickness,
-        y + thickness,
-        width - thickness * 2.0,
-        height - thickness * 2.0,
-        corner_radius,
+        x + sides.left,
+        y + sides.top,
+        width - sides.width(),
+        height - sides.height(),
+        corners,
     )?;
     ctx.set_source_rgba(bg_color.r, bg_color.g, bg_color.b, bg_color.a);
     ctx.fill()?;
@@ -437,9 +630,9 @@ pub fn cairo_rounded_bordered_filled_rectangle(
     width: f64,
     height: f64,
     fill_percent: f64,
-    border_corner_radius: f64,
-    fill_corner_radius: f64,
-    thickness: f64,
+    border_corners: &Corners,
+    fill_corners: &Corners,
+    sides: &Sides,
     fg_color: &Color,
     bg_color: &Color,
     fill_color: &Color,
@@ -448,29 +641,25 @@ pub fn cairo_rounded_bordered_filled_rectangle(
     // then paint on top of stuff later.
     ctx.push_group();
     ctx.set_operator(cairo::Operator::Source);
-    cairo_path_rounded_rectangle(ctx, x, y, width, height, border_corner_radius)?;
+    cairo_path_rounded_rectangle(ctx, x, y, width, height, border_corners)?;
     ctx.set_source_rgba(fg_color.r, fg_color.g, fg_color.b, fg_color.a);
     ctx.fill()?;
 
+    let (inner_x, inner_y) = (x + sides.left, y + sides.top);
+    let (inner_width, inner_height) = (width - sides.width(), height - sides.height());
+
     // Background clipping path (to prevent leaks at small fill %s).
-    cairo_path_rounded_rectangle(
-        ctx,
-        x + thickness,
-        y + thickness,
-        width - thickness * 2.0,
-        height - thickness * 2.0,
-        fill_corner_radius,
-    )?;
+    cairo_path_rounded_rectangle(ctx, inner_x, inner_y, inner_width, inner_height, fill_corners)?;
     ctx.clip_preserve();
 
     // Draw background, which subtracts from the clipping area path.
     cairo_path_rounded_rectangle_inverse(
         ctx,
-        x + thickness,
-        y + thickness,
-        (width - thickness * 2.0) * fill_percent,
-        height - thickness * 2.0,
-        fill_corner_radius,
+        inner_x,
+        inner_y,
+        inner_width * fill_percent,
+        inner_height,
+        fill_corners,
     )?;
     ctx.set_source_rgba(bg_color.r, bg_color.g, bg_color.b, bg_color.a);
     ctx.fill()?;
@@ -478,11 +667,11 @@ pub fn cairo_rounded_bordered_filled_rectangle(
     // Draw fill area, on top of the background.
     cairo_path_rounded_rectangle(
         ctx,
-        x + thickness,
-        y + thickness,
-        (width - thickness * 2.0) * fill_percent,
-        height - thickness * 2.0,
-        fill_corner_radius,
+        inner_x,
+        inner_y,
+        inner_width * fill_percent,
+        inner_height,
+        fill_corners,
     )?;
     ctx.set_source_rgba(fill_color.r, fill_color.g, fill_color.b, fill_color.a);
     ctx.fill()?;
@@ -803,9 +992,10 @@ pub fn query_screensaver_info(base_window: &Window) -> Result<XScreenSaverInfo,
     }
 }
 
-fn get_mouse_pos(base_window: &Window) -> (i32, i32) {
+// `None` if we aren't on X11, e.g. the base window is a Wayland one.
+fn get_mouse_pos(base_window: &Window) -> Option<(i32, i32)> {
     // Christ this feels expensive, but it's probably fine.
-    let display = window::get_xlib_display(base_window).unwrap();
+    let display = window::get_xlib_display(base_window)?;
 
     let mut _root = 0;
     let mut _child = 0;
@@ -832,13 +1022,13 @@ fn get_mouse_pos(base_window: &Window) -> (i32, i32) {
         );
     }
 
-    (mouse_x, mouse_y)
+    Some((mouse_x, mouse_y))
 }
 
 // Check if the cursor resides in each monitor rect.  This should be good
 // enough for most use cases.
 pub fn get_active_monitor_mouse(base_window: &Window) -> Option<MonitorHandle> {
-    let (x, y) = get_mouse_pos(base_window);
+    let (x, y) = get_mouse_pos(base_window)?;
     let mouse_pos = &Vec2 {
         x: x as f64,
         y: y as f64,
@@ -930,6 +1120,235 @@ pub fn get_active_monitor_keyboard(base_window: &Window) -> Option<MonitorHandle
     handle
 }
 
+// Read a property made of 32 bit values (CARDINAL, WINDOW, etc.) from a window.
+// Xlib hands these back as longs, whatever size they actually are.
+unsafe fn get_long_property(
+    display: *mut xlib::Display,
+    window: xlib::Window,
+    name: &str,
+) -> Option<Vec<c_ulong>> {
+    let name = std::ffi::CString::new(name).ok()?;
+    // Don't create the atom if it doesn't exist -- then nobody has set the property anyway.
+    let atom = xlib::XInternAtom(display, name.as_ptr(), xlib::True);
+    if atom == 0 {
+        return None;
+    }
+
+    let mut _actual_type = 0;
+    let mut actual_format = 0;
+    let mut count = 0;
+    let mut _bytes_after = 0;
+    let mut data: *mut u8 = std::ptr::null_mut();
+    let status = xlib::XGetWindowProperty(
+        display,
+        window,
+        atom,
+        0,
+        4096,
+        xlib::False,
+        xlib::AnyPropertyType as _,
+        &mut _actual_type,
+        &mut actual_format,
+        &mut count,
+        &mut _bytes_after,
+        &mut data,
+    );
+
+    if status != xlib::Success as i32 || data.is_null() {
+        return None;
+    }
+
+    let values = if actual_format == 32 {
+        Some(std::slice::from_raw_parts(data as *const c_ulong, count as usize).to_vec())
+    } else {
+        None
+    };
+
+    xlib::XFree(data as _);
+    values
+}
+
+// The part of `monitor_rect` that isn't covered by panels and docks, going by the EWMH struts they
+// set.
+// If the window manager doesn't give us a client list we fall back to `_NET_WORKAREA`, which
+// isn't per-monitor, but is better than nothing.
+pub fn get_workarea(base_window: &Window, monitor_rect: &Rect) -> Option<Rect> {
+    let display = window::get_xlib_display(base_window)?;
+
+    unsafe {
+        let screen = xlib::XDefaultScreen(display);
+        let root = xlib::XRootWindow(display, screen);
+        let root_width = xlib::XDisplayWidth(display, screen) as f64;
+        let root_height = xlib::XDisplayHeight(display, screen) as f64;
+
+        let (mut left, mut top) = (monitor_rect.left(), monitor_rect.top());
+        let (mut right, mut bottom) = (monitor_rect.right(), monitor_rect.bottom());
+
+        let clients = match get_long_property(display, root, "_NET_CLIENT_LIST") {
+            Some(clients) => clients,
+            None => {
+                let workareas = get_long_property(display, root, "_NET_WORKAREA")?;
+                let desktop = get_long_property(display, root, "_NET_CURRENT_DESKTOP")
+                    .and_then(|d| d.first().copied())
+                    .unwrap_or(0) as usize;
+                let area: Vec<f64> = workareas
+                    .get(desktop * 4..desktop * 4 + 4)?
+                    .iter()
+                    .map(|v| *v as f64)
+                    .collect();
+
+                left = left.max(area[0]);
+                top = top.max(area[1]);
+                right = right.min(area[0] + area[2]);
+                bottom = bottom.min(area[1] + area[3]);
+                vec![]
+            }
+        };
+
+        for client in clients {
+            // left, right, top, bottom, then the start and end of each edge in the same order.
+            let strut: Vec<f64> = match get_long_property(display, client, "_NET_WM_STRUT_PARTIAL") {
+                Some(s) if s.len() >= 12 => s.iter().map(|v| *v as f64).collect(),
+                _ => match get_long_property(display, client, "_NET_WM_STRUT") {
+                    // The older property always covers the whole edge.
+                    Some(s) if s.len() >= 4 => {
+                        let s: Vec<f64> = s.iter().map(|v| *v as f64).collect();
+                        vec![
+                            s[0], s[1], s[2], s[3],
+                            0.0, root_height - 1.0, 0.0, root_height - 1.0,
+                            0.0, root_width - 1.0, 0.0, root_width - 1.0,
+                        ]
+                    }
+                    _ => continue,
+                },
+            };
+
+            // Struts are relative to the edges of the whole screen, so only shrink this monitor's
+            // area if the strut actually ends inside it.
+            let r = monitor_rect;
+            let inside = |edge: f64, min: f64, max: f64| edge > min && edge < max;
+            let overlaps = |start: f64, end: f64, min: f64, max: f64| start < max && end + 1.0 > min;
+            if inside(strut[0], r.left(), r.right()) && overlaps(strut[4], strut[5], r.top(), r.bottom()) {
+                left = left.max(strut[0]);
+            }
+            let edge = root_width - strut[1];
+            if inside(edge, r.left(), r.right()) && overlaps(strut[6], strut[7], r.top(), r.bottom()) {
+                right = right.min(edge);
+            }
+            if inside(strut[2], r.top(), r.bottom()) && overlaps(strut[8], strut[9], r.left(), r.right()) {
+                top = top.max(strut[2]);
+            }
+            let edge = root_height - strut[3];
+            if inside(edge, r.top(), r.bottom()) && overlaps(strut[10], strut[11], r.left(), r.right()) {
+                bottom = bottom.min(edge);
+            }
+        }
+
+        if right <= left || bottom <= top {
+            return None;
+        }
+
+        Some(Rect::new(left, top, right - left, bottom - top))
+    }
+}
+
+// The parts of the X11 SHAPE extension that we use, from `X11/extensions/shape.h`.  The x11 crate
+// doesn't have bindings for it.
+const SHAPE_SET: c_int = 0;
+const SHAPE_BOUNDING: c_int = 0;
+const SHAPE_INPUT: c_int = 2;
+const SHAPE_UNSORTED: c_int = 0;
+
+#[link(name = "Xext")]
+extern "C" {
+    fn XShapeQueryExtension(
+        display: *mut xlib::Display,
+        event_base: *mut c_int,
+        error_base: *mut c_int,
+    ) -> xlib::Bool;
+    fn XShapeCombineRectangles(
+        display: *mut xlib::Display,
+        dest: xlib::Window,
+        dest_kind: c_int,
+        x_off: c_int,
+        y_off: c_int,
+        rectangles: *mut xlib::XRectangle,
+        n_rects: c_int,
+        op: c_int,
+        ordering: c_int,
+    );
+    fn XShapeCombineMask(
+        display: *mut xlib::Display,
+        dest: xlib::Window,
+        dest_kind: c_int,
+        x_off: c_int,
+        y_off: c_int,
+        src: xlib::Pixmap,
+        op: c_int,
+    );
+}
+
+// Which of a window's shapes to set: what's shown of it, or what gets mouse input.
+#[derive(Debug, Clone, Copy)]
+pub enum ShapeKind {
+    Bounding,
+    Input,
+}
+
+// Cut `window` down to `rects`, which are in physical pixels from its top left.  An empty list
+// leaves nothing, and `None` puts back the whole window.
+// Fails if the X server doesn't have the SHAPE extension.
+pub fn set_window_shape(
+    window: &Window,
+    kind: ShapeKind,
+    rects: Option<&[Rect]>,
+) -> Result<(), &'static str> {
+    let display = window::get_xlib_display(window).ok_or("Couldn't get xlib display")?;
+    let xlib_window = window::get_xlib_window(window).ok_or("Couldn't get xlib window")?;
+    let kind = match kind {
+        ShapeKind::Bounding => SHAPE_BOUNDING,
+        ShapeKind::Input => SHAPE_INPUT,
+    };
+
+    unsafe {
+        let (mut _event_base, mut _error_base) = (0, 0);
+        if XShapeQueryExtension(display, &mut _event_base, &mut _error_base) == xlib::False {
+            return Err("The X server doesn't support the SHAPE extension");
+        }
+
+        match rects {
+            Some(rects) => {
+                let mut rectangles: Vec<xlib::XRectangle> = rects
+                    .iter()
+                    .map(|r| xlib::XRectangle {
+                        x: r.x() as i16,
+                        y: r.y() as i16,
+                        width: r.width() as u16,
+                        height: r.height() as u16,
+                    })
+                    .collect();
+                XShapeCombineRectangles(
+                    display,
+                    xlib_window,
+                    kind,
+                    0,
+                    0,
+                    rectangles.as_mut_ptr(),
+                    rectangles.len() as c_int,
+                    SHAPE_SET,
+                    SHAPE_UNSORTED,
+                );
+            }
+            // No mask means no shape.
+            None => XShapeCombineMask(display, xlib_window, kind, 0, 0, 0, SHAPE_SET),
+        }
+
+        xlib::XFlush(display);
+    }
+
+    Ok(())
+}
+
 pub fn svg_to_pixels(data: &[u8], width: u32, height: u32) -> Option<Vec<u8>> {
     use tiny_skia::{Pixmap, Transform};
     use usvg::{FitTo, Options, Tree};
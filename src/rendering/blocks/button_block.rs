@@ -7,12 +7,11 @@ use crate::bus;
 use crate::bus::dbus_codegen::OrgFreedesktopNotificationsActionInvoked;
 use crate::config::{Color, Config, Padding};
 use crate::maths_utility;
-use crate::maths_utility::{MinMax, Rect, Vec2};
+use crate::maths_utility::{Corners, MinMax, Rect, Sides, Vec2};
 use crate::rendering::{
+    context::RenderContext,
     layout::{DrawableLayoutElement, Hook, LayoutBlock},
-    text::AlignMode,
-    text::EllipsizeMode,
-    window::NotifyWindow,
+    text::{AlignMode, EllipsizeMode, TextOutline, TextShadow},
 };
 
 #[derive(Debug, Deserialize, Clone)]
@@ -33,8 +32,9 @@ pub struct ButtonBlockParameters {
     pub action: Action,
     pub text: String,
     pub font: String,
-    pub border_width: f64,
-    pub border_rounding: f64,
+    // These can be set per side and per corner, like `NotificationBlock`'s.
+    pub border_width: Sides,
+    pub border_rounding: Corners,
     pub text_color: Color,
     pub border_color: Color,
     pub background_color: Color,
@@ -48,6 +48,9 @@ pub struct ButtonBlockParameters {
     pub ellipsize: EllipsizeMode,
     #[serde(default)]
     pub align: AlignMode,
+    // For the label, like `TextBlock`'s.
+    pub shadow: Option<TextShadow>,
+    pub outline: Option<TextOutline>,
 
     // -- Runtime fields
     #[serde(skip)]
@@ -91,14 +94,14 @@ impl DrawableLayoutElement for ButtonBlockParameters {
         hook: &Hook,
         offset: &Vec2,
         parent_rect: &Rect,
-        window: &NotifyWindow,
+        window: &dyn RenderContext,
     ) -> Result<Rect, cairo::Error> {
         let text_col = self.text_color();
         let border_col = self.border_color();
         let background_col = self.background_color();
 
         // Get would-be text pos and set the text for drawing later.
-        window.text.set_text(
+        window.text().set_text(
             &self.real_text,
             &self.font,
             self.dimensions.width.max,
@@ -106,7 +109,7 @@ impl DrawableLayoutElement for ButtonBlockParameters {
             &self.ellipsize,
             &self.align,
         );
-        let mut rect = window.text.get_sized_padded_rect(
+        let mut rect = window.text().get_sized_padded_rect(
             &self.padding,
             self.dimensions.width.min,
             self.dimensions.height.min,
@@ -115,30 +118,35 @@ impl DrawableLayoutElement for ButtonBlockParameters {
 
         // Button background.
         maths_utility::cairo_rounded_bordered_rectangle(
-            &window.context,
+            window.context(),
             pos.x,
             pos.y, // x, y
             rect.width(),
             rect.height(),
-            self.border_rounding,
-            self.border_width,
+            &self.border_rounding,
+            &self.border_width,
             border_col,
             background_col,
         )?;
 
-        window.context.set_operator(cairo::Operator::Over);
+        window.context().set_operator(cairo::Operator::Over);
         // Move block to text position (ignoring padding) for draw operation.
-        window
-            .text
-            .paint_padded(&window.context, &pos, text_col, &self.padding);
+        window.text().paint_padded(
+            window.context(),
+            &pos,
+            text_col,
+            &self.padding,
+            self.shadow.as_ref(),
+            self.outline.as_ref(),
+        )?;
 
         // Debug, unpadded drawing, to help users.
         if Config::get().debug {
             let r = window
-                .text
+                .text()
                 .get_sized_rect(self.dimensions.width.min, self.dimensions.height.min);
             maths_utility::debug_rect(
-                &window.context,
+                window.context(),
                 true,
                 pos.x + self.padding.left,
                 pos.y + self.padding.top,
@@ -156,18 +164,18 @@ impl DrawableLayoutElement for ButtonBlockParameters {
         hook: &Hook,
         offset: &Vec2,
         parent_rect: &Rect,
-        window: &NotifyWindow,
+        window: &dyn RenderContext,
     ) -> Rect {
         let maybe_action = match self.action {
-            Action::DefaultAction => window.notification.get_default_action(),
-            Action::OtherAction(i) => window.notification.get_other_action(i),
+            Action::DefaultAction => window.notification().get_default_action(),
+            Action::OtherAction(i) => window.notification().get_other_action(i),
         };
 
         let (key, text) = maybe_action.unwrap_or(("".to_owned(), "".to_owned()));
-        let text = maths_utility::format_action_notification_string(&self.text, &text, &window.notification);
+        let text = maths_utility::format_action_notification_string(&self.text, &text, window.notification());
         self.key = key;
 
-        window.text.set_text(
+        window.text().set_text(
             &text,
             &self.font,
             self.dimensions.width.max,
@@ -175,7 +183,7 @@ impl DrawableLayoutElement for ButtonBlockParameters {
             &self.ellipsize,
             &self.align,
         );
-        let mut rect = window.text.get_sized_padded_rect(
+        let mut rect = window.text().get_sized_padded_rect(
             &self.padding,
             self.dimensions.width.min,
             self.dimensions.height.min,
@@ -188,17 +196,17 @@ impl DrawableLayoutElement for ButtonBlockParameters {
         rect
     }
 
-    fn clicked(&mut self, window: &NotifyWindow) -> bool {
+    fn clicked(&mut self, window: &dyn RenderContext) -> bool {
         let message = OrgFreedesktopNotificationsActionInvoked {
             action_key: self.key.clone(),
-            id: window.notification.id,
+            id: window.notification().id,
         };
         let path = Path::new(bus::dbus::PATH).expect("Failed to create DBus path.");
         let _result = bus::dbus::get_connection().send(message.to_emit_message(&path));
         false
     }
 
-    fn hovered(&mut self, entered: bool, _window: &NotifyWindow) -> bool {
+    fn hovered(&mut self, entered: bool, _window: &dyn RenderContext) -> bool {
         self.hover = entered;
         true
     }
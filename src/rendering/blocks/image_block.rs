@@ -2,9 +2,9 @@ use serde::Deserialize;
 
 use crate::bus::dbus::ImageData;
 use crate::config::Padding;
-use crate::maths_utility::{self, Rect, Vec2};
+use crate::maths_utility::{self, Corners, Rect, Vec2};
+use crate::rendering::context::RenderContext;
 use crate::rendering::layout::{DrawableLayoutElement, Hook, LayoutBlock};
-use crate::rendering::window::NotifyWindow;
 use cairo::Format;
 use cairo::ImageSurface;
 use image::imageops::FilterType;
@@ -67,7 +67,7 @@ impl DrawableLayoutElement for ImageBlockParameters {
         hook: &Hook,
         offset: &Vec2,
         parent_rect: &Rect,
-        window: &NotifyWindow,
+        window: &dyn RenderContext,
     ) -> Result<Rect, cairo::Error> {
         // `cached_surface` should always exist on notifications with images, because we always
         // cache it.  If-let is just a precaution here.
@@ -82,19 +82,19 @@ impl DrawableLayoutElement for ImageBlockParameters {
             rect.set_xy(pos.x, pos.y);
 
             let (x, y) = (pos.x + self.padding.left, pos.y + self.padding.top);
-            window.context.set_source_surface(img_sfc, x, y)?;
+            window.context().set_source_surface(img_sfc, x, y)?;
             maths_utility::cairo_path_rounded_rectangle(
-                &window.context,
+                window.context(),
                 x,
                 y,
                 self.scale_width as f64,
                 self.scale_height as f64,
-                self.rounding,
+                &Corners::all(self.rounding),
             )?;
-            //window.context.rectangle(x, y, self.scale_width as f64, self.scale_height as f64);
-            window.context.fill()?;
+            //window.context().rectangle(x, y, self.scale_width as f64, self.scale_height as f64);
+            window.context().fill()?;
             maths_utility::debug_rect(
-                &window.context,
+                window.context(),
                 true,
                 x,
                 y,
@@ -117,36 +117,39 @@ impl DrawableLayoutElement for ImageBlockParameters {
         hook: &Hook,
         offset: &Vec2,
         parent_rect: &Rect,
-        window: &NotifyWindow,
+        window: &dyn RenderContext,
     ) -> Rect {
         let maybe_image_data = match self.image_type {
-            ImageType::App => window.notification.app_image.as_ref(),
-            ImageType::Hint => window.notification.hint_image.as_ref(),
+            ImageType::App => window.notification().app_image.as_ref(),
+            ImageType::Hint => window.notification().hint_image.as_ref(),
             ImageType::AppThenHint => window
-                .notification
+                .notification()
                 .app_image
                 .as_ref()
-                .or(window.notification.hint_image.as_ref()),
+                .or(window.notification().hint_image.as_ref()),
             ImageType::HintThenApp => window
-                .notification
+                .notification()
                 .hint_image
                 .as_ref()
-                .or(window.notification.app_image.as_ref()),
+                .or(window.notification().app_image.as_ref()),
         };
 
+        // Images are rendered at the window's physical resolution so they stay sharp when scaled up
+        // for HiDPI monitors.
+        let width = (self.scale_width as f64 * window.scale()).round() as i32;
+        let height = (self.scale_height as f64 * window.scale()).round() as i32;
+
         let maybe_pixels = if let Some(data) = maybe_image_data {
             match data {
                 ImageData::Dynamic(img) => {
                     let filter_type = self.filter_mode.to_image_mode();
                     let px = img
-                        .resize_exact(self.scale_width as u32, self.scale_height as u32, filter_type)
+                        .resize_exact(width as u32, height as u32, filter_type)
                         .to_bgra8() // Cairo reads pixels back-to-front, so ARgb32 is actually BgrA32.
                         .into_raw();
                     Some(px)
                 }
-                ImageData::SVG(data) => {
-                    maths_utility::svg_to_pixels(data, self.scale_width as u32, self.scale_height as u32)
-                }
+                ImageData::SVG(data) => maths_utility::svg_to_pixels(data, width as u32, height as u32),
             }
         } else {
             None
@@ -161,17 +164,12 @@ impl DrawableLayoutElement for ImageBlockParameters {
             );
 
             let pos = LayoutBlock::find_anchor_pos(hook, offset, parent_rect, &rect);
-            let stride = cairo::Format::stride_for_width(Format::ARgb32, self.scale_width as u32)
+            let stride = cairo::Format::stride_for_width(Format::ARgb32, width as u32)
                 .expect("Failed to calculate image stride.");
 
-            let image_sfc = ImageSurface::create_for_data(
-                pixels,
-                Format::ARgb32,
-                self.scale_width,
-                self.scale_height,
-                stride,
-            )
-            .expect("Failed to create image surface.");
+            let image_sfc = ImageSurface::create_for_data(pixels, Format::ARgb32, width, height, stride)
+                .expect("Failed to create image surface.");
+            image_sfc.set_device_scale(window.scale(), window.scale());
 
             self.cached_surface = Some(image_sfc);
 
@@ -4,6 +4,7 @@ pub mod notification_block;
 pub mod progress_block;
 pub mod scrolling_text_block;
 pub mod text_block;
+pub mod timeout_block;
 
 pub use button_block::*;
 pub use image_block::*;
@@ -11,3 +12,4 @@ pub use notification_block::*;
 pub use progress_block::*;
 pub use scrolling_text_block::*;
 pub use text_block::*;
+pub use timeout_block::*;
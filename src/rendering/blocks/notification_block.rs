@@ -1,19 +1,121 @@
+use std::fmt::{self, Formatter};
 use std::time::Duration;
 
-use serde::Deserialize;
+use serde::{de, Deserialize, Deserializer};
 
 use crate::bus::dbus::Urgency;
-use crate::config::Color;
-use crate::maths_utility::{self, Rect, Vec2};
+use crate::config::{Color, TimeoutPolicy};
+use crate::maths_utility::{self, Corners, Rect, Sides, Vec2};
 use crate::rendering::layout::{DrawableLayoutElement, Hook};
-use crate::rendering::window::{NotifyWindow, UpdateModes};
+use crate::rendering::{context::RenderContext, fill::Fill, window::UpdateModes};
+
+// A monitor to show notifications on.
+// Deserializes from an index into the list of monitors (negative follows the active monitor), a
+// RandR output name like "DP-2", or one of "primary" and "active".
+#[derive(Debug, Clone, PartialEq)]
+pub enum Monitor {
+    Index(usize),
+    Name(String),
+    Primary,
+    Active,
+}
+
+impl<'de> Deserialize<'de> for Monitor {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_any(MonitorVisitor)
+    }
+}
+
+struct MonitorVisitor;
+
+impl<'de> de::Visitor<'de> for MonitorVisitor {
+    type Value = Monitor;
+
+    fn expecting(&self, f: &mut Formatter) -> fmt::Result {
+        write!(f, "a monitor index, an output name, \"primary\" or \"active\"")
+    }
+
+    fn visit_i64<E: de::Error>(self, v: i64) -> Result<Self::Value, E> {
+        if v < 0 {
+            Ok(Monitor::Active)
+        } else {
+            Ok(Monitor::Index(v as usize))
+        }
+    }
+
+    fn visit_u64<E: de::Error>(self, v: u64) -> Result<Self::Value, E> {
+        Ok(Monitor::Index(v as usize))
+    }
+
+    fn visit_str<E: de::Error>(self, v: &str) -> Result<Self::Value, E> {
+        Ok(match v {
+            "primary" => Monitor::Primary,
+            "active" => Monitor::Active,
+            name => Monitor::Name(name.to_owned()),
+        })
+    }
+}
+
+// Deserializes either a single monitor or a list of them, in order of preference.
+fn deserialize_monitors<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<Monitor>, D::Error> {
+    struct MonitorsVisitor;
+
+    impl<'de> de::Visitor<'de> for MonitorsVisitor {
+        type Value = Vec<Monitor>;
+
+        fn expecting(&self, f: &mut Formatter) -> fmt::Result {
+            write!(f, "a monitor, or a list of monitors")
+        }
+
+        fn visit_i64<E: de::Error>(self, v: i64) -> Result<Self::Value, E> {
+            MonitorVisitor.visit_i64(v).map(|m| vec![m])
+        }
+
+        fn visit_u64<E: de::Error>(self, v: u64) -> Result<Self::Value, E> {
+            MonitorVisitor.visit_u64(v).map(|m| vec![m])
+        }
+
+        fn visit_str<E: de::Error>(self, v: &str) -> Result<Self::Value, E> {
+            MonitorVisitor.visit_str(v).map(|m| vec![m])
+        }
+
+        fn visit_seq<A: de::SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+            let mut monitors = vec![];
+            while let Some(monitor) = seq.next_element()? {
+                monitors.push(monitor);
+            }
+            Ok(monitors)
+        }
+    }
+
+    deserializer.deserialize_any(MonitorsVisitor)
+}
+
+// Monitors to show a layout on at once, see `NotificationBlockParameters::mirror`.
+#[derive(Debug, Deserialize, Clone)]
+pub enum Mirror {
+    All,
+    Monitors(Vec<Monitor>),
+}
 
 #[derive(Debug, Deserialize, Clone)]
 pub struct NotificationBlockParameters {
-    pub monitor: i8,
+    // The first of these that's connected is used, falling back to the primary monitor.
+    #[serde(deserialize_with = "deserialize_monitors")]
+    pub monitor: Vec<Monitor>,
+    // Show a copy of each notification on several monitors, instead of just `monitor`.
+    // Dismissing any copy dismisses them all.
+    pub mirror: Option<Mirror>,
+    // Anchor to the part of the monitor that isn't taken up by panels and docks, instead of the
+    // whole monitor.
+    #[serde(default)]
+    pub use_workarea: bool,
 
-    pub border_width: f64,
-    pub border_rounding: f64,
+    // Either one for everything, or e.g. `(left: 0.0, right: 3.0, top: 3.0, bottom: 3.0)` and
+    // `(top_right: 3.0, bottom_right: 3.0)` to sit flush against the left edge of the screen.
+    pub border_width: Sides,
+    pub border_rounding: Corners,
+    // Round the outside of the border, rather than just the inside.
     #[serde(default)]
     pub rounded_border_corners: bool,
     pub background_color: Color,
@@ -26,87 +128,185 @@ pub struct NotificationBlockParameters {
     pub border_color_critical: Option<Color>,
     pub border_color_paused: Option<Color>,
 
+    // Gradients or images, drawn over `background_color` and `border_color`, and laid out over the
+    // whole notification.  `border_fill` isn't drawn while one of the other border colors is in
+    // use, so urgency and pausing still show.
+    pub background_fill: Option<Fill>,
+    pub border_fill: Option<Fill>,
+
+    // Overrides the config's timeout settings for notifications using this layout.
+    #[serde(default)]
+    pub timeouts: TimeoutPolicy,
+
+    // Only take clicks and hovers on what's actually drawn, so the rounded off corners (or with a
+    // transparent background, everything between the blocks) don't get in the way.  X11 only.
+    #[serde(default)]
+    pub shape_input: bool,
+    // Cut the window itself down to what's drawn too, for when there's no compositor to blend the
+    // corners.  The edges won't be antialiased.  X11 only.
+    #[serde(default)]
+    pub shape_window: bool,
+    // Don't take any clicks or hovers at all, so the notification is just for show.
+    #[serde(default)]
+    pub click_through: bool,
+
     #[serde(skip)]
     current_update_mode: UpdateModes,
 }
 
+impl NotificationBlockParameters {
+    // Whether this layout (or any copy of it) follows the active monitor.
+    pub fn follows_active_monitor(&self) -> bool {
+        match &self.mirror {
+            Some(Mirror::All) => false,
+            Some(Mirror::Monitors(monitors)) => monitors.contains(&Monitor::Active),
+            None => self.monitor.contains(&Monitor::Active),
+        }
+    }
+}
+
+impl NotificationBlockParameters {
+    // The corners of the outside of the border, and the rect and corners inside it, for a
+    // notification that's `width` x `height`.
+    fn outline(&self, width: f64, height: f64) -> (Corners, Rect, Corners) {
+        let bw = &self.border_width;
+        let inner = Rect::new(bw.left, bw.top, width - bw.width(), height - bw.height());
+
+        let outer_corners = if self.rounded_border_corners {
+            self.border_rounding.clamped(width, height)
+        } else {
+            Corners::default()
+        };
+        let inner_corners = self
+            .border_rounding
+            .inset(bw)
+            .clamped(inner.width(), inner.height());
+
+        (outer_corners, inner, inner_corners)
+    }
+
+    // The part of a `width` x `height` notification that anything is drawn on, as a rounded rect.
+    // `None` if the border and background are both completely transparent, in which case it's
+    // just whatever the other blocks draw.
+    pub fn drawn_outline(&self, width: f64, height: f64) -> Option<(Rect, Corners)> {
+        let visible = |color: Option<&Color>| color.is_some_and(|c| c.a > 0.0);
+        let border_visible = self.border_width.width() + self.border_width.height() > 0.0
+            && (self.border_fill.is_some()
+                || [
+                    Some(&self.border_color),
+                    self.border_color_low.as_ref(),
+                    self.border_color_critical.as_ref(),
+                    self.border_color_paused.as_ref(),
+                ]
+                .into_iter()
+                .any(visible));
+        let background_visible = self.background_fill.is_some() || visible(Some(&self.background_color));
+
+        let (outer_corners, inner, inner_corners) = self.outline(width, height);
+        if border_visible {
+            Some((Rect::new(0.0, 0.0, width, height), outer_corners))
+        } else if background_visible {
+            Some((inner, inner_corners))
+        } else {
+            None
+        }
+    }
+
+    // Fill the current path with the current source, and then with `fill` on top of that.
+    fn fill_over(ctx: &cairo::Context, fill: Option<&Fill>, rect: &Rect) -> Result<(), cairo::Error> {
+        match fill {
+            Some(fill) => {
+                ctx.fill_preserve()?;
+                ctx.set_operator(cairo::Operator::Over);
+                fill.set_source(ctx, rect)?;
+                ctx.fill()?;
+                ctx.set_operator(cairo::Operator::Source);
+                Ok(())
+            }
+            None => ctx.fill(),
+        }
+    }
+}
+
 impl DrawableLayoutElement for NotificationBlockParameters {
     fn draw(
         &self,
         _hook: &Hook,
         _offset: &Vec2,
         parent_rect: &Rect,
-        window: &NotifyWindow,
+        window: &dyn RenderContext,
     ) -> Result<Rect, cairo::Error> {
         // Clear
-        window.context.set_operator(cairo::Operator::Clear);
-        window.context.paint()?;
+        window.context().set_operator(cairo::Operator::Clear);
+        window.context().paint()?;
 
-        window.context.set_operator(cairo::Operator::Source);
+        window.context().set_operator(cairo::Operator::Source);
 
         // Draw border + background.
         // If anything isn't updating, we count it as paused, which overrides urgency.
         // Otherwise, we evaluate urgency.
-        let bd_color = {
-            if window.update_mode != UpdateModes::all() {
-                self.border_color_paused.as_ref().unwrap_or(&self.border_color)
+        let bd_override = {
+            if window.update_mode() != UpdateModes::all() {
+                self.border_color_paused.as_ref()
             } else {
-                match window.notification.urgency {
-                    Urgency::Low => self.border_color_low.as_ref().unwrap_or(&self.border_color),
-                    Urgency::Normal => &self.border_color,
-                    Urgency::Critical => self.border_color_critical.as_ref().unwrap_or(&self.border_color),
+                match window.notification().urgency {
+                    Urgency::Low => self.border_color_low.as_ref(),
+                    Urgency::Normal => None,
+                    Urgency::Critical => self.border_color_critical.as_ref(),
                 }
             }
         };
+        let bd_color = bd_override.unwrap_or(&self.border_color);
+        let bd_fill = if bd_override.is_none() {
+            self.border_fill.as_ref()
+        } else {
+            None
+        };
 
         //let bd_color = &self.border_color;
         let bg_color = &self.background_color;
-        let bw = &self.border_width;
-        let radius = &self.border_rounding;
         let w = parent_rect.width();
         let h = parent_rect.height();
-
-        let outer_radius = if self.rounded_border_corners {
-            radius.min(w.min(h) / 2.0)
-        } else {
-            0.0
-        };
-        let inner_radius = (radius - bw).max(0.0).min((w - bw * 2.0).min(h - bw * 2.0) / 2.0);
+        let (outer_corners, inner, inner_corners) = self.outline(w, h);
 
         // Draw border
-        window.context.new_path();
-        maths_utility::cairo_path_rounded_rectangle(&window.context, 0.0, 0.0, w, h, outer_radius)?;
+        window.context().new_path();
+        maths_utility::cairo_path_rounded_rectangle(window.context(), 0.0, 0.0, w, h, &outer_corners)?;
 
-        window.context.new_sub_path();
+        window.context().new_sub_path();
         maths_utility::cairo_path_rounded_rectangle(
-            &window.context,
-            *bw,
-            *bw, // x, y
-            w - bw * 2.0,
-            h - bw * 2.0,
-            inner_radius,
+            window.context(),
+            inner.x(),
+            inner.y(),
+            inner.width(),
+            inner.height(),
+            &inner_corners,
         )?;
         window
-            .context
+            .context()
             .set_source_rgba(bd_color.r, bd_color.g, bd_color.b, bd_color.a);
-        window.context.set_fill_rule(cairo::FillRule::EvenOdd);
-        window.context.fill()?;
+        window.context().set_fill_rule(cairo::FillRule::EvenOdd);
+        Self::fill_over(window.context(), bd_fill, &Rect::new(0.0, 0.0, w, h))?;
 
         // Draw background
         maths_utility::cairo_path_rounded_rectangle(
-            &window.context,
-            *bw,
-            *bw, // x, y
-            w - bw * 2.0,
-            h - bw * 2.0,
-            inner_radius,
+            window.context(),
+            inner.x(),
+            inner.y(),
+            inner.width(),
+            inner.height(),
+            &inner_corners,
         )?;
 
         window
-            .context
+            .context()
             .set_source_rgba(bg_color.r, bg_color.g, bg_color.b, bg_color.a);
-        window.context.set_fill_rule(cairo::FillRule::Winding);
-        window.context.fill()?;
+        window.context().set_fill_rule(cairo::FillRule::Winding);
+        Self::fill_over(
+            window.context(),
+            self.background_fill.as_ref(),
+            &Rect::new(0.0, 0.0, w, h),
+        )?;
 
         Ok(Rect::new(
             parent_rect.x(),
@@ -121,9 +321,10 @@ impl DrawableLayoutElement for NotificationBlockParameters {
         _hook: &Hook,
         _offset: &Vec2,
         parent_rect: &Rect,
-        window: &NotifyWindow,
+        window: &dyn RenderContext,
     ) -> Rect {
-        self.current_update_mode = window.update_mode;
+        self.current_update_mode = window.update_mode();
+
         Rect::new(
             parent_rect.x(),
             parent_rect.y(),
@@ -132,9 +333,9 @@ impl DrawableLayoutElement for NotificationBlockParameters {
         )
     }
 
-    fn update(&mut self, _delta_time: Duration, window: &NotifyWindow) -> bool {
-        if window.update_mode != self.current_update_mode {
-            self.current_update_mode = window.update_mode;
+    fn update(&mut self, _delta_time: Duration, window: &dyn RenderContext) -> bool {
+        if window.update_mode() != self.current_update_mode {
+            self.current_update_mode = window.update_mode();
             return true;
         }
 
@@ -2,18 +2,18 @@ use serde::Deserialize;
 
 use crate::config::{Color, Config, Padding};
 use crate::maths_utility;
-use crate::maths_utility::{Rect, Vec2};
+use crate::maths_utility::{Corners, Rect, Sides, Vec2};
 use crate::rendering::{
+    context::RenderContext,
     layout::{DrawableLayoutElement, Hook, LayoutBlock},
-    window::NotifyWindow,
 };
 
 #[derive(Debug, Deserialize, Clone)]
 pub struct ProgressBlockParameters {
     pub padding: Padding,
-    pub border_width: f64,
-    pub border_rounding: f64,
-    pub fill_rounding: f64,
+    pub border_width: Sides,
+    pub border_rounding: Corners,
+    pub fill_rounding: Corners,
     pub border_color: Color,
     pub background_color: Color,
     pub fill_color: Color,
@@ -65,7 +65,7 @@ impl DrawableLayoutElement for ProgressBlockParameters {
         hook: &Hook,
         offset: &Vec2,
         parent_rect: &Rect,
-        window: &NotifyWindow,
+        window: &dyn RenderContext,
     ) -> Result<Rect, cairo::Error> {
         let border_col = self.border_color();
         let background_col = self.background_color();
@@ -86,25 +86,25 @@ impl DrawableLayoutElement for ProgressBlockParameters {
 
         // Progress background.
         maths_utility::cairo_rounded_bordered_filled_rectangle(
-            &window.context,
+            window.context(),
             pos.x + self.padding.left,
             pos.y + self.padding.top, // x, y
             width - self.padding.width(),
             height - self.padding.height(),
             self.percentage,
-            self.border_rounding,
-            self.fill_rounding,
-            self.border_width,
+            &self.border_rounding,
+            &self.fill_rounding,
+            &self.border_width,
             border_col,
             background_col,
             fill_col,
         )?;
 
-        window.context.set_operator(cairo::Operator::Over);
+        window.context().set_operator(cairo::Operator::Over);
         // Debug, unpadded drawing, to help users.
         if Config::get().debug {
             maths_utility::debug_rect(
-                &window.context,
+                window.context(),
                 true,
                 pos.x + self.padding.left,
                 pos.y + self.padding.top,
@@ -122,7 +122,7 @@ impl DrawableLayoutElement for ProgressBlockParameters {
         hook: &Hook,
         offset: &Vec2,
         parent_rect: &Rect,
-        window: &NotifyWindow,
+        window: &dyn RenderContext,
     ) -> Rect {
         if self.padding.width() > parent_rect.width() || self.padding.height() > parent_rect.height() {
             eprintln!("Warning: padding width/height exceeds parent rect width/height.");
@@ -140,13 +140,13 @@ impl DrawableLayoutElement for ProgressBlockParameters {
         };
         let mut rect = Rect::new(0.0, 0.0, width, height);
 
-        self.percentage = window.notification.percentage.unwrap_or(0.0) as f64;
+        self.percentage = window.notification().percentage.unwrap_or(0.0) as f64;
         let pos = LayoutBlock::find_anchor_pos(hook, offset, parent_rect, &rect);
         rect.set_xy(pos.x, pos.y);
         rect
     }
 
-    fn hovered(&mut self, entered: bool, _window: &NotifyWindow) -> bool {
+    fn hovered(&mut self, entered: bool, _window: &dyn RenderContext) -> bool {
         self.hover = entered;
         true
     }
@@ -6,9 +6,9 @@ use std::time::Duration;
 use crate::bus::dbus::Notification;
 use crate::config::{Color, Config, Padding};
 use crate::maths_utility::{self, MinMax, Rect, Vec2};
+use crate::rendering::context::RenderContext;
 use crate::rendering::layout::{DrawableLayoutElement, Hook, LayoutBlock};
-use crate::rendering::text::{AlignMode, EllipsizeMode};
-use crate::rendering::window::NotifyWindow;
+use crate::rendering::text::{AlignMode, EllipsizeMode, TextOutline, TextShadow};
 
 #[derive(Debug, Deserialize, Clone)]
 pub struct ScrollingTextBlockParameters {
@@ -31,6 +31,9 @@ pub struct ScrollingTextBlockParameters {
     pub width_image_both: Option<MinMax>,
     #[serde(default)]
     pub backtrack_scroll_pos: bool,
+    // Same as `TextBlock`'s.
+    pub shadow: Option<TextShadow>,
+    pub outline: Option<TextOutline>,
 
     // -- Runtime fields
     #[serde(skip)]
@@ -72,7 +75,7 @@ impl DrawableLayoutElement for ScrollingTextBlockParameters {
         hook: &Hook,
         offset: &Vec2,
         parent_rect: &Rect,
-        window: &NotifyWindow,
+        window: &dyn RenderContext,
     ) -> Result<Rect, cairo::Error> {
         let width = &self.real_width;
 
@@ -81,7 +84,7 @@ impl DrawableLayoutElement for ScrollingTextBlockParameters {
         // We could cache this rect, but haven't yet.
         // We need to set some ellipsize mode, or the text size will be forced larger despite our
         // max width/height.
-        window.text.set_text(
+        window.text().set_text(
             &self.real_text,
             &self.font,
             width.max,
@@ -89,10 +92,10 @@ impl DrawableLayoutElement for ScrollingTextBlockParameters {
             &EllipsizeMode::Middle,
             &AlignMode::Left,
         );
-        let mut rect = window.text.get_sized_padded_rect(&self.padding, width.min, 0);
+        let mut rect = window.text().get_sized_padded_rect(&self.padding, width.min, 0);
 
         // Set the text to the real (scrolling) string.
-        window.text.set_text(
+        window.text().set_text(
             &self.real_text,
             &self.font,
             -1,
@@ -106,7 +109,7 @@ impl DrawableLayoutElement for ScrollingTextBlockParameters {
         pos.y += self.padding.top;
         // Debug, unpadded drawing, to help users.
         maths_utility::debug_rect(
-            &window.context,
+            window.context(),
             true,
             pos.x,
             pos.y,
@@ -123,9 +126,9 @@ impl DrawableLayoutElement for ScrollingTextBlockParameters {
         // text's x position really.
         if self.text_rect.width() > width.max as f64 {
             window
-                .context
+                .context()
                 .rectangle(pos.x, pos.y, self.clip_rect.width(), self.clip_rect.height());
-            window.context.clip();
+            window.context().clip();
 
             // @TODO: also add dynamic scroll option.
             // Equivalent to clip_rect.left() + self.lhs_dist if clip_rect had correct coordinates.
@@ -139,10 +142,22 @@ impl DrawableLayoutElement for ScrollingTextBlockParameters {
             // Keep track of pos.x; it's important for the layout.
             let temp = pos.x;
             pos.x = lerp;
-            window.text.paint(&window.context, &pos, col);
+            window.text().paint(
+                window.context(),
+                &pos,
+                col,
+                self.shadow.as_ref(),
+                self.outline.as_ref(),
+            )?;
             pos.x = temp;
         } else {
-            window.text.paint(&window.context, &pos, col);
+            window.text().paint(
+                window.context(),
+                &pos,
+                col,
+                self.shadow.as_ref(),
+                self.outline.as_ref(),
+            )?;
         }
 
         pos.x -= self.padding.left;
@@ -157,16 +172,16 @@ impl DrawableLayoutElement for ScrollingTextBlockParameters {
         hook: &Hook,
         offset: &Vec2,
         parent_rect: &Rect,
-        window: &NotifyWindow,
+        window: &dyn RenderContext,
     ) -> Rect {
-        let text = maths_utility::format_notification_string(&self.text, &window.notification);
+        let text = maths_utility::format_notification_string(&self.text, window.notification());
 
         // We cache real_width because we need to access it in `update()` later, which doesn't have
         // access to the notification.
-        self.real_width = self.get_width(&window.notification).clone();
+        self.real_width = self.get_width(window.notification()).clone();
 
         // Max height of 0 = one line of text.
-        window.text.set_text(
+        window.text().set_text(
             &text,
             &self.font,
             self.real_width.max,
@@ -179,12 +194,12 @@ impl DrawableLayoutElement for ScrollingTextBlockParameters {
         // `clip_rect` -- Unpadded rect, used for clipping.
         // `text_rect` -- Real text rect, with infinite length.
         let mut rect = window
-            .text
+            .text()
             .get_sized_padded_rect(&self.padding, self.real_width.min, 0);
         let clip_rect = window
-            .text
+            .text()
             .get_sized_padded_rect(&Padding::new(0.0, 0.0, 0.0, 0.0), 0, 0);
-        window.text.set_text(
+        window.text().set_text(
             &text,
             &self.font,
             -1,
@@ -192,7 +207,7 @@ impl DrawableLayoutElement for ScrollingTextBlockParameters {
             &EllipsizeMode::NoEllipsize,
             &AlignMode::Left,
         );
-        let text_rect = window.text.get_sized_padded_rect(&self.padding, 0, 0);
+        let text_rect = window.text().get_sized_padded_rect(&self.padding, 0, 0);
 
         if text_rect.width() > self.real_width.max as f64 {
             self.update_enabled = true;
@@ -217,7 +232,7 @@ impl DrawableLayoutElement for ScrollingTextBlockParameters {
         // not really be able to tell.
         if self.backtrack_scroll_pos {
             let now = Local::now();
-            let delta = (now - window.creation_timestamp).num_milliseconds();
+            let delta = (now - window.creation_timestamp()).num_milliseconds();
             let mut delta = u64::try_from(delta).unwrap_or(0);
 
             let cfg = Config::get();
@@ -231,7 +246,7 @@ impl DrawableLayoutElement for ScrollingTextBlockParameters {
         rect
     }
 
-    fn update(&mut self, delta_time: Duration, _window: &NotifyWindow) -> bool {
+    fn update(&mut self, delta_time: Duration, _window: &dyn RenderContext) -> bool {
         if !self.update_enabled {
             return false;
         }
@@ -258,12 +273,12 @@ impl DrawableLayoutElement for ScrollingTextBlockParameters {
         true
     }
 
-    fn clicked(&mut self, _window: &NotifyWindow) -> bool {
+    fn clicked(&mut self, _window: &dyn RenderContext) -> bool {
         maths_utility::find_and_open_url(self.real_text.clone());
         false
     }
 
-    fn hovered(&mut self, entered: bool, _window: &NotifyWindow) -> bool {
+    fn hovered(&mut self, entered: bool, _window: &dyn RenderContext) -> bool {
         self.hover = entered;
         true
     }
@@ -5,10 +5,9 @@ use crate::config::{Color, Config, Padding};
 use crate::maths_utility;
 use crate::maths_utility::{MinMax, Rect, Vec2};
 use crate::rendering::{
+    context::RenderContext,
     layout::{DrawableLayoutElement, Hook, LayoutBlock},
-    text::AlignMode,
-    text::EllipsizeMode,
-    window::NotifyWindow,
+    text::{self, AlignMode, EllipsizeMode, TextNormalization, TextOutline, TextShadow, WrapMode},
 };
 
 #[derive(Debug, Deserialize, Clone)]
@@ -35,6 +34,21 @@ pub struct TextBlockParameters {
     pub ellipsize: EllipsizeMode,
     #[serde(default)]
     pub align: AlignMode,
+    #[serde(default)]
+    pub wrap: WrapMode,
+    // Extra space between lines, in pixels.
+    #[serde(default)]
+    pub line_spacing: f64,
+    pub max_lines: Option<u32>,
+    // Stretch lines out to fill the width, aligning both edges.
+    #[serde(default)]
+    pub justify: bool,
+    // Applied to the text after it's been formatted.
+    #[serde(default)]
+    pub normalize: Vec<TextNormalization>,
+    // Drawn behind and around the text, e.g. to keep it readable on translucent backgrounds.
+    pub shadow: Option<TextShadow>,
+    pub outline: Option<TextOutline>,
 
     // -- Runtime fields
     #[serde(skip)]
@@ -57,30 +71,39 @@ impl TextBlockParameters {
     }
 }
 
+impl TextBlockParameters {
+    fn set_text(&self, text: &str, dimensions: &Dimensions, window: &dyn RenderContext) {
+        window.text().set_text(
+            text,
+            &self.font,
+            dimensions.width.max,
+            dimensions.height.max,
+            &self.ellipsize,
+            &self.align,
+        );
+        window
+            .text()
+            .set_paragraph(&self.wrap, self.line_spacing, self.justify, self.max_lines);
+    }
+}
+
 impl DrawableLayoutElement for TextBlockParameters {
     fn draw(
         &self,
         hook: &Hook,
         offset: &Vec2,
         parent_rect: &Rect,
-        window: &NotifyWindow,
+        window: &dyn RenderContext,
     ) -> Result<Rect, cairo::Error> {
         // This is implicit in >0.10.1.
-        //window.context.set_operator(cairo::Operator::Over);
+        //window.context().set_operator(cairo::Operator::Over);
 
-        let dimensions = self.get_dimensions(&window.notification);
+        let dimensions = self.get_dimensions(window.notification());
 
-        window.text.set_text(
-            &self.real_text,
-            &self.font,
-            dimensions.width.max,
-            dimensions.height.max,
-            &self.ellipsize,
-            &self.align,
-        );
+        self.set_text(&self.real_text, dimensions, window);
         let mut rect =
             window
-                .text
+                .text()
                 .get_sized_padded_rect(&self.padding, dimensions.width.min, dimensions.height.min);
 
         let pos = LayoutBlock::find_anchor_pos(hook, offset, parent_rect, &rect);
@@ -91,16 +114,21 @@ impl DrawableLayoutElement for TextBlockParameters {
             &self.color
         };
         // Move block to text position (ignoring padding) for draw operation.
-        window
-            .text
-            .paint_padded(&window.context, &pos, col, &self.padding);
+        window.text().paint_padded(
+            window.context(),
+            &pos,
+            col,
+            &self.padding,
+            self.shadow.as_ref(),
+            self.outline.as_ref(),
+        )?;
         // Debug, unpadded drawing, to help users.
         if Config::get().debug {
             let r = window
-                .text
+                .text()
                 .get_sized_rect(dimensions.width.min, dimensions.height.min);
             maths_utility::debug_rect(
-                &window.context,
+                window.context(),
                 true,
                 pos.x + self.padding.left,
                 pos.y + self.padding.top,
@@ -118,22 +146,16 @@ impl DrawableLayoutElement for TextBlockParameters {
         hook: &Hook,
         offset: &Vec2,
         parent_rect: &Rect,
-        window: &NotifyWindow,
+        window: &dyn RenderContext,
     ) -> Rect {
-        let text = maths_utility::format_notification_string(&self.text, &window.notification);
+        let text = maths_utility::format_notification_string(&self.text, window.notification());
+        let text = text::normalize(&text, &self.normalize);
 
-        let dimensions = self.get_dimensions(&window.notification);
-        window.text.set_text(
-            &text,
-            &self.font,
-            dimensions.width.max,
-            dimensions.height.max,
-            &self.ellipsize,
-            &self.align,
-        );
+        let dimensions = self.get_dimensions(window.notification());
+        self.set_text(&text, dimensions, window);
         let mut rect =
             window
-                .text
+                .text()
                 .get_sized_padded_rect(&self.padding, dimensions.width.min, dimensions.height.min);
 
         self.real_text = text;
@@ -143,12 +165,12 @@ impl DrawableLayoutElement for TextBlockParameters {
         rect
     }
 
-    fn clicked(&mut self, _window: &NotifyWindow) -> bool {
+    fn clicked(&mut self, _window: &dyn RenderContext) -> bool {
         maths_utility::find_and_open_url(self.real_text.clone());
         false
     }
 
-    fn hovered(&mut self, entered: bool, _window: &NotifyWindow) -> bool {
+    fn hovered(&mut self, entered: bool, _window: &dyn RenderContext) -> bool {
         self.hover = entered;
         true
     }
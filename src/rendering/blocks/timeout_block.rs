@@ -0,0 +1,280 @@
+use std::f64::consts::PI;
+use std::time::Duration;
+
+use serde::Deserialize;
+
+use crate::bus::dbus::Timeout;
+use crate::config::{Color, Config, Padding};
+use crate::maths_utility;
+use crate::maths_utility::{Corners, Rect, Sides, Vec2};
+use crate::rendering::{
+    context::RenderContext,
+    layout::{DrawableLayoutElement, Hook, LayoutBlock},
+    window::UpdateModes,
+};
+
+#[derive(Debug, Deserialize, Clone, Default)]
+pub enum TimeoutShape {
+    // Shrinks from right to left.
+    #[default]
+    Bar,
+    // Empties back towards the top.
+    Ring,
+}
+
+// Shows how long is left before the notification times out.
+// Notifications that never expire always show as full.
+#[derive(Debug, Deserialize, Clone)]
+pub struct TimeoutBlockParameters {
+    pub padding: Padding,
+    pub width: f64,
+    pub height: f64,
+    pub background_color: Color,
+    pub fill_color: Color,
+
+    // -- Optional fields
+    #[serde(default)]
+    pub shape: TimeoutShape,
+    // Used instead of `fill_color` while the notification is paused.
+    pub fill_color_paused: Option<Color>,
+    // `Bar` only.
+    #[serde(default)]
+    pub border_width: Sides,
+    #[serde(default)]
+    pub border_rounding: Corners,
+    #[serde(default)]
+    pub fill_rounding: Corners,
+    pub border_color: Option<Color>,
+    // `Ring` only.  Defaults to a fifth of the ring's size.
+    pub ring_width: Option<f64>,
+
+    // -- Runtime fields
+    #[serde(skip)]
+    remaining: f64,
+    #[serde(skip)]
+    paused: bool,
+    #[serde(skip)]
+    fill_length: f64,
+}
+
+impl TimeoutBlockParameters {
+    fn fill_color(&self) -> &Color {
+        if self.paused {
+            self.fill_color_paused.as_ref().unwrap_or(&self.fill_color)
+        } else {
+            &self.fill_color
+        }
+    }
+
+    fn size(&self, parent_rect: &Rect) -> (f64, f64) {
+        let width = if self.width < 0.0 {
+            parent_rect.width()
+        } else {
+            self.width + self.padding.width()
+        };
+        let height = if self.height < 0.0 {
+            parent_rect.height()
+        } else {
+            self.height + self.padding.height()
+        };
+
+        (width, height)
+    }
+
+    fn line_width(&self, size: f64) -> f64 {
+        self.ring_width.unwrap_or(size * 0.2)
+    }
+
+    // How many pixels the fill covers when it's full: the bar's width, or the ring's circumference.
+    fn fill_length(&self, parent_rect: &Rect) -> f64 {
+        let (width, height) = self.size(parent_rect);
+        let (w, h) = (width - self.padding.width(), height - self.padding.height());
+        match self.shape {
+            TimeoutShape::Bar => w,
+            TimeoutShape::Ring => {
+                let size = w.min(h);
+                PI * (size - self.line_width(size))
+            }
+        }
+    }
+}
+
+// Whether going from `old` to `new` moves the fill by at least a pixel, for a fill that's `length`
+// pixels long.  Running out is always shown.
+fn fuse_change_visible(old: f64, new: f64, length: f64) -> bool {
+    new != old && ((new - old).abs() * length.max(1.0) >= 1.0 || new == 0.0)
+}
+
+// How much of the fuse is left, from 0.0 to 1.0.
+fn remaining_fuse(window: &dyn RenderContext) -> f64 {
+    match (window.fuse(), window.fuse_total()) {
+        (Timeout::Milliseconds(left), Timeout::Milliseconds(total)) if *total > 0 => {
+            maths_utility::clamp(*left as f64 / *total as f64, 0.0, 1.0)
+        }
+        _ => 1.0,
+    }
+}
+
+// Much of this is the same as ProgressBlock, see there and TextBlock for documentation.
+impl DrawableLayoutElement for TimeoutBlockParameters {
+    fn draw(
+        &self,
+        hook: &Hook,
+        offset: &Vec2,
+        parent_rect: &Rect,
+        window: &dyn RenderContext,
+    ) -> Result<Rect, cairo::Error> {
+        let (width, height) = self.size(parent_rect);
+        let mut rect = Rect::new(0.0, 0.0, width, height);
+        let pos = LayoutBlock::find_anchor_pos(hook, offset, parent_rect, &rect);
+
+        let (x, y) = (pos.x + self.padding.left, pos.y + self.padding.top);
+        let (w, h) = (width - self.padding.width(), height - self.padding.height());
+        let bg = &self.background_color;
+        let fill = self.fill_color();
+
+        match self.shape {
+            TimeoutShape::Bar => {
+                maths_utility::cairo_rounded_bordered_filled_rectangle(
+                    window.context(),
+                    x,
+                    y,
+                    w,
+                    h,
+                    self.remaining,
+                    &self.border_rounding,
+                    &self.fill_rounding,
+                    &self.border_width,
+                    self.border_color.as_ref().unwrap_or(bg),
+                    bg,
+                    fill,
+                )?;
+            }
+            TimeoutShape::Ring => {
+                let size = w.min(h);
+                let line_width = self.line_width(size);
+                let radius = (size - line_width) * 0.5;
+                let (cx, cy) = (x + w * 0.5, y + h * 0.5);
+                let top = -PI * 0.5;
+
+                window.context().set_line_width(line_width);
+                window.context().new_path();
+                window.context().arc(cx, cy, radius, 0.0, PI * 2.0);
+                window.context().set_source_rgba(bg.r, bg.g, bg.b, bg.a);
+                window.context().stroke()?;
+
+                if self.remaining > 0.0 {
+                    window.context().new_path();
+                    window
+                        .context()
+                        .arc(cx, cy, radius, top, top + PI * 2.0 * self.remaining);
+                    window.context().set_source_rgba(fill.r, fill.g, fill.b, fill.a);
+                    window.context().stroke()?;
+                }
+            }
+        }
+
+        // Debug, unpadded drawing, to help users.
+        if Config::get().debug {
+            maths_utility::debug_rect(window.context(), true, x, y, w, h)?;
+        }
+
+        rect.set_xy(pos.x, pos.y);
+        Ok(rect)
+    }
+
+    fn predict_rect_and_init(
+        &mut self,
+        hook: &Hook,
+        offset: &Vec2,
+        parent_rect: &Rect,
+        window: &dyn RenderContext,
+    ) -> Rect {
+        if self.padding.width() > parent_rect.width() || self.padding.height() > parent_rect.height() {
+            eprintln!("Warning: padding width/height exceeds parent rect width/height.");
+        }
+
+        self.remaining = remaining_fuse(window);
+        self.paused = !window.update_mode().contains(UpdateModes::FUSE);
+        self.fill_length = self.fill_length(parent_rect);
+
+        let (width, height) = self.size(parent_rect);
+        let mut rect = Rect::new(0.0, 0.0, width, height);
+        let pos = LayoutBlock::find_anchor_pos(hook, offset, parent_rect, &rect);
+        rect.set_xy(pos.x, pos.y);
+        rect
+    }
+
+    fn update(&mut self, _delta_time: Duration, window: &dyn RenderContext) -> bool {
+        let remaining = remaining_fuse(window);
+        let paused = !window.update_mode().contains(UpdateModes::FUSE);
+
+        // Only redraw once the change would actually be visible (about a pixel), rather than every
+        // frame.
+        if paused != self.paused || fuse_change_visible(self.remaining, remaining, self.fill_length) {
+            self.remaining = remaining;
+            self.paused = paused;
+            return true;
+        }
+
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn params(shape: TimeoutShape, width: f64, height: f64) -> TimeoutBlockParameters {
+        TimeoutBlockParameters {
+            padding: Padding::new(5.0, 5.0, 2.0, 2.0),
+            width,
+            height,
+            background_color: Color::from_rgba(0.0, 0.0, 0.0, 1.0),
+            fill_color: Color::from_rgba(1.0, 1.0, 1.0, 1.0),
+            shape,
+            fill_color_paused: None,
+            border_width: Sides::default(),
+            border_rounding: Corners::default(),
+            fill_rounding: Corners::default(),
+            border_color: None,
+            ring_width: None,
+            remaining: 1.0,
+            paused: false,
+            fill_length: 0.0,
+        }
+    }
+
+    #[test]
+    fn bar_length() {
+        let parent = Rect::new(0.0, 0.0, 300.0, 50.0);
+        assert_eq!(params(TimeoutShape::Bar, 100.0, 4.0).fill_length(&parent), 100.0);
+        // Filling the parent, less padding.
+        assert_eq!(params(TimeoutShape::Bar, -1.0, 4.0).fill_length(&parent), 290.0);
+    }
+
+    #[test]
+    fn ring_length() {
+        let parent = Rect::new(0.0, 0.0, 300.0, 50.0);
+        // 40px across once the padding's gone, with an 8px line leaves a 16px radius.
+        let length = params(TimeoutShape::Ring, 40.0, 40.0).fill_length(&parent);
+        assert!((length - PI * 32.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn redraws_fill_parent_bar() {
+        let parent = Rect::new(0.0, 0.0, 300.0, 50.0);
+        let length = params(TimeoutShape::Bar, -1.0, 4.0).fill_length(&parent);
+
+        // A pixel and a bit across 290px, then a fraction of one.
+        assert!(fuse_change_visible(1.0, 0.995, length));
+        assert!(!fuse_change_visible(1.0, 0.999, length));
+    }
+
+    #[test]
+    fn redraws_when_run_out() {
+        assert!(fuse_change_visible(0.001, 0.0, 100.0));
+        assert!(!fuse_change_visible(0.0, 0.0, 100.0));
+        assert!(!fuse_change_visible(0.5, 0.5, 100.0));
+    }
+}
@@ -0,0 +1,30 @@
+use cairo::Context;
+use chrono::{DateTime, Local};
+
+use crate::{
+    bus::dbus::{Notification, Timeout},
+    maths_utility::Vec2,
+    rendering::{text::TextRenderer, window::UpdateModes},
+};
+
+// Everything a layout needs to draw itself, without caring where the drawing ends up.
+// `NotifyWindow` is the main implementation, drawing to X11 windows or Wayland surfaces, and
+// `OffscreenRender` draws to an image in memory.
+pub trait RenderContext {
+    fn context(&self) -> &Context;
+    fn text(&self) -> &TextRenderer;
+    fn notification(&self) -> &Notification;
+
+    // Layouts are drawn in logical pixels, which are this many physical pixels on the surface.
+    // Only matters for things drawn at a fixed resolution, like images.
+    fn scale(&self) -> f64;
+    // Applied to the whole layout when it's drawn, for fading.
+    fn opacity(&self) -> f64;
+    fn update_mode(&self) -> UpdateModes;
+    // How long is left before the notification times out, and what that started at.
+    fn fuse(&self) -> &Timeout;
+    fn fuse_total(&self) -> &Timeout;
+    fn creation_timestamp(&self) -> DateTime<Local>;
+    // Where the cursor last was, in logical pixels from the top left.
+    fn mouse_pos(&self) -> &Vec2;
+}
@@ -0,0 +1,188 @@
+use std::path::Path;
+
+use cairo::{Extend, Format, ImageSurface, LinearGradient, Matrix, RadialGradient, SurfacePattern};
+use home_dir::HomeDirExt;
+use serde::Deserialize;
+
+use crate::{
+    config::Color,
+    maths_utility::{Rect, Vec2},
+};
+
+// How an image is fit into the area it fills.
+#[derive(Debug, Deserialize, Clone, Default)]
+pub enum ImageScale {
+    // Squash or stretch the image to exactly fit.
+    Stretch,
+    // Scale the image to cover the whole area, cutting off whatever sticks out.
+    #[default]
+    Cover,
+    // Scale the image to fit entirely inside the area.
+    Contain,
+    // Repeat the image at its original size.
+    Tile,
+    // Show the image at its original size, in the middle.
+    Center,
+}
+
+// Something other than a plain color to fill a shape with.
+// Positions are fractions of the area being filled, so `Vec2(x: 0.0, y: 0.0)` is the top left and
+// `Vec2(x: 1.0, y: 1.0)` is the bottom right.
+#[derive(Debug, Deserialize, Clone)]
+pub enum Fill {
+    // Fades between `colors` (spread out evenly) along the line from `start` to `end`.
+    LinearGradient {
+        start: Vec2,
+        end: Vec2,
+        colors: Vec<Color>,
+    },
+    // Fades between `colors` (spread out evenly) out from `center`.  `radius` is a fraction of
+    // the longer side.
+    RadialGradient {
+        center: Vec2,
+        radius: f64,
+        colors: Vec<Color>,
+    },
+    // An image file (PNG, JPEG, etc.).  Paths should be absolute, or start with `~`.
+    Image {
+        path: String,
+        #[serde(default)]
+        scale: ImageScale,
+
+        // Loaded by `load`.  Cairo surfaces are reference counted, so copies of the layout share it.
+        #[serde(skip)]
+        surface: Option<ImageSurface>,
+    },
+}
+
+impl Fill {
+    // Load anything the fill needs ahead of drawing, and check that it can actually be drawn.
+    // This is done once, when the config is loaded, rather than for every notification.
+    pub fn load(&mut self) -> Result<(), String> {
+        match self {
+            Fill::LinearGradient { colors, .. } | Fill::RadialGradient { colors, .. } => {
+                if colors.is_empty() {
+                    Err("gradient needs at least one color.".to_owned())
+                } else {
+                    Ok(())
+                }
+            }
+            Fill::Image { path, surface, .. } => {
+                if surface.is_none() {
+                    let image =
+                        load_image(path).map_err(|e| format!("couldn't load image {}: {}", path, e))?;
+                    *surface = Some(image);
+                }
+                Ok(())
+            }
+        }
+    }
+
+    // Every color in the fill, so they can be resolved against `styles` and checked.
+    pub fn colors_mut(&mut self) -> Vec<&mut Color> {
+        match self {
+            Fill::LinearGradient { colors, .. } | Fill::RadialGradient { colors, .. } => {
+                colors.iter_mut().collect()
+            }
+            Fill::Image { .. } => vec![],
+        }
+    }
+
+    // Set the fill as the context's source, laid out over `rect`.
+    // Images need `load` to have been called first, otherwise nothing is set.
+    pub fn set_source(&self, ctx: &cairo::Context, rect: &Rect) -> Result<(), cairo::Error> {
+        let at = |v: &Vec2| (rect.x() + v.x * rect.width(), rect.y() + v.y * rect.height());
+
+        match self {
+            Fill::LinearGradient { start, end, colors } => {
+                let ((x0, y0), (x1, y1)) = (at(start), at(end));
+                let gradient = LinearGradient::new(x0, y0, x1, y1);
+                add_color_stops(&gradient, colors);
+                ctx.set_source(&gradient)?;
+            }
+            Fill::RadialGradient {
+                center,
+                radius,
+                colors,
+            } => {
+                let (x, y) = at(center);
+                let radius = radius * rect.width().max(rect.height());
+                let gradient = RadialGradient::new(x, y, 0.0, x, y, radius);
+                add_color_stops(&gradient, colors);
+                ctx.set_source(&gradient)?;
+            }
+            Fill::Image {
+                scale,
+                surface: Some(surface),
+                ..
+            } => {
+                let (width, height) = (surface.width() as f64, surface.height() as f64);
+                let (scale_x, scale_y) = match scale {
+                    ImageScale::Stretch => (rect.width() / width, rect.height() / height),
+                    ImageScale::Cover => {
+                        let s = (rect.width() / width).max(rect.height() / height);
+                        (s, s)
+                    }
+                    ImageScale::Contain => {
+                        let s = (rect.width() / width).min(rect.height() / height);
+                        (s, s)
+                    }
+                    ImageScale::Tile | ImageScale::Center => (1.0, 1.0),
+                };
+
+                // Centered, unless it's tiled from the top left.
+                let (x, y) = match scale {
+                    ImageScale::Tile => (rect.x(), rect.y()),
+                    _ => (
+                        rect.x() + (rect.width() - width * scale_x) / 2.0,
+                        rect.y() + (rect.height() - height * scale_y) / 2.0,
+                    ),
+                };
+
+                // Patterns are transformed from user space to the image, so this is backwards.
+                let pattern = SurfacePattern::create(surface);
+                let mut matrix = Matrix::identity();
+                matrix.scale(1.0 / scale_x, 1.0 / scale_y);
+                matrix.translate(-x, -y);
+                pattern.set_matrix(matrix);
+                if let ImageScale::Tile = scale {
+                    pattern.set_extend(Extend::Repeat);
+                }
+                ctx.set_source(&pattern)?;
+            }
+            Fill::Image { surface: None, .. } => (),
+        }
+
+        Ok(())
+    }
+}
+
+fn add_color_stops(gradient: &cairo::Gradient, colors: &[Color]) {
+    // A single color is still a valid gradient, it's just not very interesting.
+    let last = (colors.len() - 1).max(1) as f64;
+    for (i, c) in colors.iter().enumerate() {
+        gradient.add_color_stop_rgba(i as f64 / last, c.r, c.g, c.b, c.a);
+    }
+}
+
+fn load_image(path: &str) -> Result<ImageSurface, String> {
+    let path = Path::new(path).expand_home().map_err(|e| e.to_string())?;
+    let image = image::open(path).map_err(|e| e.to_string())?.to_rgba8();
+    let (width, height) = image.dimensions();
+
+    let stride = Format::ARgb32
+        .stride_for_width(width)
+        .map_err(|e| e.to_string())?;
+    let mut data = vec![0; stride as usize * height as usize];
+    for (x, y, pixel) in image.enumerate_pixels() {
+        // Cairo wants premultiplied alpha, in native endian ARGB (so BGRA on little endian).
+        let [r, g, b, a] = pixel.0;
+        let premultiply = |c: u8| (c as u32 * a as u32 / 255) as u8;
+        let argb = u32::from_be_bytes([a, premultiply(r), premultiply(g), premultiply(b)]);
+        let i = y as usize * stride as usize + x as usize * 4;
+        data[i..i + 4].copy_from_slice(&argb.to_ne_bytes());
+    }
+
+    ImageSurface::create_for_data(data, Format::ARgb32, width as i32, height as i32, stride)
+        .map_err(|e| e.to_string())
+}
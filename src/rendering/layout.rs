@@ -1,13 +1,13 @@
-use std::time::Duration;
+use std::{collections::HashMap, time::Duration};
 
 use serde::Deserialize;
 
 use crate::{
     bus::dbus::{Notification, Urgency},
-    config::{AnchorPosition, Config},
+    config::{AnchorPosition, Color, Config, Padding},
     maths_utility::{Rect, Vec2},
     rendering::blocks::*,
-    rendering::window::NotifyWindow,
+    rendering::context::RenderContext,
 };
 
 use wired_derive::DrawableLayoutElement;
@@ -28,6 +28,10 @@ pub struct LayoutBlock {
     pub render_criteria: Vec<RenderCriteria>,
     #[serde(default)]
     pub render_anti_criteria: Vec<RenderCriteria>,
+    // Parameters to use instead for notifications of a particular urgency, e.g. a red background
+    // for critical notifications.  Applied when the notification's window is created.
+    #[serde(default)]
+    pub urgency_overrides: UrgencyOverrides,
     #[serde(skip)]
     pub children: Vec<LayoutBlock>,
 
@@ -63,6 +67,103 @@ enum Logic {
     Or,
 }
 
+// Overrides for each urgency, like `TimeoutPolicy`.
+#[derive(Debug, Deserialize, Default, Clone)]
+pub struct UrgencyOverrides {
+    pub low: Option<ParamOverrides>,
+    pub normal: Option<ParamOverrides>,
+    pub critical: Option<ParamOverrides>,
+}
+
+impl UrgencyOverrides {
+    pub fn get(&self, urgency: &Urgency) -> Option<&ParamOverrides> {
+        match urgency {
+            Urgency::Low => self.low.as_ref(),
+            Urgency::Normal => self.normal.as_ref(),
+            Urgency::Critical => self.critical.as_ref(),
+        }
+    }
+
+    // Each set of overrides, along with the urgency it's for.
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = (&'static str, &mut ParamOverrides)> {
+        [
+            ("low", self.low.as_mut()),
+            ("normal", self.normal.as_mut()),
+            ("critical", self.critical.as_mut()),
+        ]
+        .into_iter()
+        .filter_map(|(urgency, o)| o.map(|o| (urgency, o)))
+    }
+}
+
+// Values to replace a block's parameters with, keyed by the parameter's name, e.g.
+// `colors: { "background_color": Color(hex: "#fb4934") }`.
+// Only parameters that the block has set can be overridden.  Fills can't be: their stops all go by
+// the fill's name, so overriding them would flatten a gradient into one color.
+#[derive(Debug, Deserialize, Default, Clone)]
+pub struct ParamOverrides {
+    #[serde(default)]
+    pub colors: HashMap<String, Color>,
+    #[serde(default)]
+    pub fonts: HashMap<String, String>,
+    #[serde(default)]
+    pub paddings: HashMap<String, Padding>,
+}
+
+impl ParamOverrides {
+    pub fn apply(&self, params: &mut LayoutElement) {
+        for (field, color) in Self::colors_mut(params) {
+            if let Some(c) = self.colors.get(field) {
+                *color = c.clone();
+            }
+        }
+
+        for (field, font) in params.fonts_mut() {
+            if let Some(f) = self.fonts.get(field) {
+                *font = f.clone();
+            }
+        }
+
+        for (field, padding) in params.paddings_mut() {
+            if let Some(p) = self.paddings.get(field) {
+                *padding = p.clone();
+            }
+        }
+    }
+
+    // Names that don't match any parameter the block has set, which would otherwise be silently
+    // ignored.
+    pub fn unknown_fields(&self, params: &mut LayoutElement) -> Vec<String> {
+        let colors: Vec<&str> = Self::colors_mut(params).into_iter().map(|(f, _)| f).collect();
+        let fonts: Vec<&str> = params.fonts_mut().into_iter().map(|(f, _)| f).collect();
+        let paddings: Vec<&str> = params.paddings_mut().into_iter().map(|(f, _)| f).collect();
+
+        let unknown = |names: Vec<&String>, known: &[&str]| -> Vec<String> {
+            names
+                .into_iter()
+                .filter(|n| !known.contains(&n.as_str()))
+                .cloned()
+                .collect()
+        };
+        let mut fields: Vec<String> = unknown(self.colors.keys().collect(), &colors)
+            .into_iter()
+            .chain(unknown(self.fonts.keys().collect(), &fonts))
+            .chain(unknown(self.paddings.keys().collect(), &paddings))
+            .collect();
+        fields.sort();
+        fields
+    }
+
+    // The block's colors, minus any fill stops.
+    fn colors_mut(params: &mut LayoutElement) -> Vec<(&'static str, &mut Color)> {
+        params
+            .colors_mut()
+            .into_iter()
+            .filter(|(field, _)| !matches!(*field, "background_fill" | "border_fill"))
+            .collect()
+    }
+}
+
 #[derive(Debug, Deserialize, Clone)]
 pub struct Hook {
     pub parent_anchor: AnchorPosition,
@@ -85,6 +186,7 @@ pub enum LayoutElement {
     ImageBlock(ImageBlockParameters),
     ButtonBlock(ButtonBlockParameters),
     ProgressBlock(ProgressBlockParameters),
+    TimeoutBlock(TimeoutBlockParameters),
 }
 
 impl LayoutBlock {
@@ -181,7 +283,7 @@ impl LayoutBlock {
     // Call draw on each block in tree.
     pub fn draw_tree(
         &mut self,
-        window: &NotifyWindow,
+        window: &dyn RenderContext,
         parent_rect: &Rect,
         accum_rect: Rect,
         parent_is_root: bool,
@@ -194,7 +296,7 @@ impl LayoutBlock {
         // in-between one of our draws, which would be bad: https://www.cairographics.org/Xlib/
         // (Animations and Full Screen section)
         if self.parent.is_empty() {
-            window.context.push_group();
+            window.context().push_group();
         }
 
         let is_root = self.parent.is_empty();
@@ -225,7 +327,7 @@ impl LayoutBlock {
         // be kept around because we're going to move away from something recursive eventually...
         // right?
         let (rect, acc_rect) = {
-            let rect = if self.should_draw(&window.notification) {
+            let rect = if self.should_draw(window.notification()) {
                 self.params
                     .draw(&self.hook, &self.offset, parent_rect_fixed, window)
                     .expect("Invalid cairo surface state.")
@@ -243,12 +345,12 @@ impl LayoutBlock {
             // Draw debug rect around bounding box.
             if Config::get().debug {
                 let c = &Config::get().debug_color;
-                window.context.set_source_rgba(c.r, c.g, c.b, c.a);
-                window.context.set_line_width(1.0);
+                window.context().set_source_rgba(c.r, c.g, c.b, c.a);
+                window.context().set_line_width(1.0);
                 window
-                    .context
+                    .context()
                     .rectangle(rect.x(), rect.y(), rect.width(), rect.height());
-                window.context.stroke().expect("Invalid cairo surface state.");
+                window.context().stroke().expect("Invalid cairo surface state.");
             }
 
             for child in &mut self.children {
@@ -261,12 +363,24 @@ impl LayoutBlock {
         // The push group from earlier gets popped and all the drawing is done at once.
         if self.parent.is_empty() {
             window
-                .context
+                .context()
                 .pop_group_to_source()
                 .expect("Failed to pop group to source.");
-            window.context.set_operator(cairo::Operator::Source);
-            window.context.paint().expect("Invalid cairo surface state.");
-            window.context.set_operator(cairo::Operator::Over);
+            if window.opacity() < 1.0 {
+                // Fading relies on the window's alpha channel.  Painting with `Source` and an alpha
+                // would leave some of the last draw behind, so we clear it out first.
+                window.context().set_operator(cairo::Operator::Clear);
+                window.context().paint().expect("Invalid cairo surface state.");
+                window.context().set_operator(cairo::Operator::Over);
+                window
+                    .context()
+                    .paint_with_alpha(window.opacity())
+                    .expect("Invalid cairo surface state.");
+            } else {
+                window.context().set_operator(cairo::Operator::Source);
+                window.context().paint().expect("Invalid cairo surface state.");
+                window.context().set_operator(cairo::Operator::Over);
+            }
         }
 
         self.cache_rect = rect;
@@ -276,7 +390,7 @@ impl LayoutBlock {
     // Predict the size of an entire layout, and initialize elements.
     pub fn predict_rect_tree_and_init(
         &mut self,
-        window: &NotifyWindow,
+        window: &dyn RenderContext,
         parent_rect: &Rect,
         accum_rect: Rect,
     ) -> Rect {
@@ -285,7 +399,7 @@ impl LayoutBlock {
         // here to save performance.
         // `predict_rect_and_init` finds the bounding box of an individual element -- children are not
         // involved.
-        let rect = if self.should_draw(&window.notification) {
+        let rect = if self.should_draw(window.notification()) {
             self.params
                 .predict_rect_and_init(&self.hook, &self.offset, parent_rect, window)
         } else {
@@ -303,7 +417,7 @@ impl LayoutBlock {
     }
 
     // Call update on each block in tree.
-    pub fn update_tree(&mut self, delta_time: Duration, window: &NotifyWindow) -> bool {
+    pub fn update_tree(&mut self, delta_time: Duration, window: &dyn RenderContext) -> bool {
         let mut dirty = self.params.update(delta_time, window);
         for elem in &mut self.children {
             dirty |= elem.update_tree(delta_time, window);
@@ -312,20 +426,22 @@ impl LayoutBlock {
         dirty
     }
 
-    pub fn check_and_send_click(&mut self, position: &Vec2, window: &NotifyWindow) -> bool {
+    // Send a click to every block under the cursor.
+    pub fn check_and_send_click(&mut self, window: &dyn RenderContext) -> bool {
         let mut dirty = false;
-        if self.cache_rect.contains_point(position) {
+        if self.cache_rect.contains_point(window.mouse_pos()) {
             dirty |= self.params.clicked(window);
         }
 
         for child in &mut self.children {
-            dirty |= child.check_and_send_click(position, window);
+            dirty |= child.check_and_send_click(window);
         }
 
         dirty
     }
 
-    pub fn check_and_send_hover(&mut self, position: &Vec2, window: &NotifyWindow) -> bool {
+    pub fn check_and_send_hover(&mut self, window: &dyn RenderContext) -> bool {
+        let position = window.mouse_pos();
         let mut dirty = false;
         // If we aren't hovered already, and we enter the rect, then send event.
         // If we are hovered already, and we leave the rect, then send event.
@@ -338,12 +454,37 @@ impl LayoutBlock {
         }
 
         for child in &mut self.children {
-            dirty |= child.check_and_send_hover(position, window);
+            dirty |= child.check_and_send_hover(window);
         }
 
         dirty
     }
 
+    // Swap in the overrides for `urgency`, for this block and all of its children.
+    pub fn apply_urgency_tree(&mut self, urgency: &Urgency) {
+        if let Some(overrides) = self.urgency_overrides.get(urgency) {
+            overrides.apply(&mut self.params);
+        }
+
+        for child in &mut self.children {
+            child.apply_urgency_tree(urgency);
+        }
+    }
+
+    // The rects that this block's descendants were last drawn in, leaving out any that weren't
+    // drawn at all.
+    pub fn drawn_child_rects(&self) -> Vec<Rect> {
+        let mut rects = vec![];
+        for child in &self.children {
+            if child.cache_rect.width() > 0.0 && child.cache_rect.height() > 0.0 {
+                rects.push(child.cache_rect.clone());
+            }
+            rects.extend(child.drawn_child_rects());
+        }
+
+        rects
+    }
+
     pub fn as_notification_block(&self) -> &NotificationBlockParameters {
         if let LayoutElement::NotificationBlock(p) = &self.params {
             p
@@ -353,28 +494,122 @@ impl LayoutBlock {
     }
 }
 
+impl LayoutElement {
+    // Every font this element uses, along with the field it came from, so they can be resolved
+    // against `styles` and checked.
+    pub fn fonts_mut(&mut self) -> Vec<(&'static str, &mut String)> {
+        match self {
+            LayoutElement::TextBlock(p) => vec![("font", &mut p.font)],
+            LayoutElement::ScrollingTextBlock(p) => vec![("font", &mut p.font)],
+            LayoutElement::ButtonBlock(p) => vec![("font", &mut p.font)],
+            _ => vec![],
+        }
+    }
+
+    // Every color this element uses, along with the field it came from, so they can be resolved
+    // against `styles` and checked.
+    pub fn colors_mut(&mut self) -> Vec<(&'static str, &mut Color)> {
+        let colors = match self {
+            LayoutElement::NotificationBlock(p) => {
+                let mut colors = vec![
+                    ("background_color", Some(&mut p.background_color)),
+                    ("border_color", Some(&mut p.border_color)),
+                    ("border_color_low", p.border_color_low.as_mut()),
+                    ("border_color_critical", p.border_color_critical.as_mut()),
+                    ("border_color_paused", p.border_color_paused.as_mut()),
+                ];
+                for (field, fill) in [
+                    ("background_fill", p.background_fill.as_mut()),
+                    ("border_fill", p.border_fill.as_mut()),
+                ] {
+                    if let Some(fill) = fill {
+                        colors.extend(fill.colors_mut().into_iter().map(|c| (field, Some(c))));
+                    }
+                }
+                colors
+            }
+            LayoutElement::TextBlock(p) => vec![
+                ("color", Some(&mut p.color)),
+                ("color_hovered", p.color_hovered.as_mut()),
+                ("shadow.color", p.shadow.as_mut().map(|s| &mut s.color)),
+                ("outline.color", p.outline.as_mut().map(|o| &mut o.color)),
+            ],
+            LayoutElement::ScrollingTextBlock(p) => vec![
+                ("color", Some(&mut p.color)),
+                ("color_hovered", p.color_hovered.as_mut()),
+                ("shadow.color", p.shadow.as_mut().map(|s| &mut s.color)),
+                ("outline.color", p.outline.as_mut().map(|o| &mut o.color)),
+            ],
+            LayoutElement::ImageBlock(_) => vec![],
+            LayoutElement::ButtonBlock(p) => vec![
+                ("text_color", Some(&mut p.text_color)),
+                ("border_color", Some(&mut p.border_color)),
+                ("background_color", Some(&mut p.background_color)),
+                ("text_color_hovered", p.text_color_hovered.as_mut()),
+                ("border_color_hovered", p.border_color_hovered.as_mut()),
+                ("background_color_hovered", p.background_color_hovered.as_mut()),
+                ("shadow.color", p.shadow.as_mut().map(|s| &mut s.color)),
+                ("outline.color", p.outline.as_mut().map(|o| &mut o.color)),
+            ],
+            LayoutElement::ProgressBlock(p) => vec![
+                ("border_color", Some(&mut p.border_color)),
+                ("background_color", Some(&mut p.background_color)),
+                ("fill_color", Some(&mut p.fill_color)),
+                ("border_color_hovered", p.border_color_hovered.as_mut()),
+                ("background_color_hovered", p.background_color_hovered.as_mut()),
+                ("fill_color_hovered", p.fill_color_hovered.as_mut()),
+            ],
+            LayoutElement::TimeoutBlock(p) => vec![
+                ("background_color", Some(&mut p.background_color)),
+                ("fill_color", Some(&mut p.fill_color)),
+                ("fill_color_paused", p.fill_color_paused.as_mut()),
+                ("border_color", p.border_color.as_mut()),
+            ],
+        };
+
+        colors
+            .into_iter()
+            .filter_map(|(field, color)| color.map(|c| (field, c)))
+            .collect()
+    }
+
+    // Every padding this element uses, along with the field it came from, so they can be resolved
+    // against `styles`.
+    pub fn paddings_mut(&mut self) -> Vec<(&'static str, &mut Padding)> {
+        match self {
+            LayoutElement::NotificationBlock(_) => vec![],
+            LayoutElement::TextBlock(p) => vec![("padding", &mut p.padding)],
+            LayoutElement::ScrollingTextBlock(p) => vec![("padding", &mut p.padding)],
+            LayoutElement::ImageBlock(p) => vec![("padding", &mut p.padding)],
+            LayoutElement::ButtonBlock(p) => vec![("padding", &mut p.padding)],
+            LayoutElement::ProgressBlock(p) => vec![("padding", &mut p.padding)],
+            LayoutElement::TimeoutBlock(p) => vec![("padding", &mut p.padding)],
+        }
+    }
+}
+
 pub trait DrawableLayoutElement {
     fn draw(
         &self,
         hook: &Hook,
         offset: &Vec2,
         parent_rect: &Rect,
-        window: &NotifyWindow,
+        window: &dyn RenderContext,
     ) -> Result<Rect, cairo::Error>;
     fn predict_rect_and_init(
         &mut self,
         hook: &Hook,
         offset: &Vec2,
         parent_rect: &Rect,
-        window: &NotifyWindow,
+        window: &dyn RenderContext,
     ) -> Rect;
-    fn update(&mut self, _delta_time: Duration, _window: &NotifyWindow) -> bool {
+    fn update(&mut self, _delta_time: Duration, _window: &dyn RenderContext) -> bool {
         false
     }
-    fn clicked(&mut self, _window: &NotifyWindow) -> bool {
+    fn clicked(&mut self, _window: &dyn RenderContext) -> bool {
         false
     }
-    fn hovered(&mut self, _entered: bool, _window: &NotifyWindow) -> bool {
+    fn hovered(&mut self, _entered: bool, _window: &dyn RenderContext) -> bool {
         false
     }
 }
@@ -1,4 +1,11 @@
 pub mod blocks;
+pub mod context;
+pub mod fill;
 pub mod layout;
+pub mod offscreen;
 pub mod text;
+pub mod wayland;
 pub mod window;
+
+#[cfg(test)]
+mod snapshot_tests;
@@ -0,0 +1,149 @@
+use cairo::{Context, Format, ImageSurface};
+use chrono::{DateTime, Local};
+
+use crate::{
+    bus::dbus::{Notification, Timeout},
+    config::Config,
+    maths_utility::{Rect, Vec2},
+    rendering::{
+        context::RenderContext,
+        layout::LayoutBlock,
+        text::TextRenderer,
+        window::{self, UpdateModes},
+    },
+};
+
+// Draws layouts into an image in memory, so they can be rendered without a display server, e.g.
+// for `--render`.
+// Layouts are drawn as they would look the moment a notification spawns, or `elapsed`
+// milliseconds after that.
+pub struct OffscreenRender {
+    // Like `NotifyWindow`, the context goes first so that it's dropped before the surface.
+    context: Context,
+    image: ImageSurface,
+    text: TextRenderer,
+    notification: Notification,
+    scale: f64,
+    fuse: Timeout,
+    fuse_total: Timeout,
+    elapsed: i32,
+    creation_timestamp: DateTime<Local>,
+    mouse_pos: Vec2,
+}
+
+impl OffscreenRender {
+    pub fn new(notification: Notification, scale: f64) -> Self {
+        // Replaced with one that fits once we know what we're drawing.
+        let image = ImageSurface::create(Format::ARgb32, 1, 1).expect("Failed to create image surface.");
+        let context = Context::new(&image).expect("Failed to create cairo context.");
+        let text = TextRenderer::new(&context);
+
+        Self {
+            context,
+            image,
+            text,
+            notification,
+            scale,
+            fuse: Timeout::NeverExpire,
+            fuse_total: Timeout::NeverExpire,
+            elapsed: 0,
+            creation_timestamp: Local::now(),
+            // Nowhere near the layout, so nothing is hovered.
+            mouse_pos: Vec2::new(-1.0, -1.0),
+        }
+    }
+
+    // How long the notification has been up for when it's drawn, in milliseconds, which shows in
+    // anything that draws the fuse (e.g. `TimeoutBlock`).
+    pub fn set_elapsed(&mut self, elapsed: i32) {
+        self.elapsed = elapsed;
+    }
+
+    // Lay out and draw `layout` to a new image that fits it exactly.  Returns the layout's
+    // rect, in logical pixels.
+    // This follows what `NotifyWindow` does when it's created and then drawn.
+    pub fn render(&mut self, layout: &mut LayoutBlock) -> Rect {
+        let cfg = Config::get();
+        self.fuse_total = window::resolve_fuse(&self.notification, layout);
+        self.fuse = match self.fuse_total {
+            Timeout::Milliseconds(ms) => Timeout::Milliseconds((ms - self.elapsed).max(0)),
+            ref fuse => fuse.clone(),
+        };
+        layout.apply_urgency_tree(&self.notification.urgency);
+
+        let (width, height) = (
+            (cfg.min_window_width as f64).max(1.0),
+            (cfg.min_window_height as f64).max(1.0),
+        );
+        let min_rect = Rect::new(0.0, 0.0, width, height);
+        let rect = layout.predict_rect_tree_and_init(self, &min_rect, min_rect.clone());
+
+        let (physical_width, physical_height) = (
+            (rect.width() * self.scale).ceil(),
+            (rect.height() * self.scale).ceil(),
+        );
+        self.image = ImageSurface::create(Format::ARgb32, physical_width as i32, physical_height as i32)
+            .expect("Failed to create image surface.");
+        self.image.set_device_scale(self.scale, self.scale);
+        self.context = Context::new(&self.image).expect("Failed to create cairo context.");
+        self.text.update_context(&self.context);
+
+        // Anything that extends left or up from the root gets shifted back onto the image, like
+        // `NotifyWindow::master_offset`.
+        let inner_rect = Rect::new(-rect.x(), -rect.y(), rect.width(), rect.height());
+        layout.draw_tree(self, &inner_rect, Rect::empty(), false);
+        self.image.flush();
+
+        rect
+    }
+
+    // Take the image, dropping the context so that nothing else holds onto it and its data can
+    // be read.
+    pub fn into_image(self) -> ImageSurface {
+        let Self { context, image, .. } = self;
+        drop(context);
+        image
+    }
+}
+
+impl RenderContext for OffscreenRender {
+    fn context(&self) -> &Context {
+        &self.context
+    }
+
+    fn text(&self) -> &TextRenderer {
+        &self.text
+    }
+
+    fn notification(&self) -> &Notification {
+        &self.notification
+    }
+
+    fn scale(&self) -> f64 {
+        self.scale
+    }
+
+    fn opacity(&self) -> f64 {
+        1.0
+    }
+
+    fn update_mode(&self) -> UpdateModes {
+        UpdateModes::all()
+    }
+
+    fn fuse(&self) -> &Timeout {
+        &self.fuse
+    }
+
+    fn fuse_total(&self) -> &Timeout {
+        &self.fuse_total
+    }
+
+    fn creation_timestamp(&self) -> DateTime<Local> {
+        self.creation_timestamp
+    }
+
+    fn mouse_pos(&self) -> &Vec2 {
+        &self.mouse_pos
+    }
+}
@@ -0,0 +1,343 @@
+// Draws each kind of block offscreen and compares the result against reference images in
+// `tests/snapshots`, along with the size that was predicted for it.
+// The layouts come from `tests/snapshots/config.ron`.
+//
+// To update the references after an intended change (or add new ones), run:
+//     WIRED_UPDATE_SNAPSHOTS=1 cargo test
+// and check the new images over before committing them.  Otherwise a missing reference fails.
+// The references are drawn with "DejaVu Sans", which has to be installed for them to match.
+// When a snapshot doesn't match, what was actually drawn is written next to the reference as
+// `<name>.new.png`.
+
+use std::fs::File;
+use std::path::PathBuf;
+use std::sync::Once;
+
+use cairo::ImageSurface;
+use image::{DynamicImage, Rgba, RgbaImage};
+
+use crate::{
+    bus::dbus::{ImageData, Notification, Timeout, Urgency},
+    config::Config,
+    rendering::{layout::LayoutBlock, offscreen::OffscreenRender, text},
+};
+
+// How far a channel can be off by before a pixel counts as different.  Allows for small rounding
+// differences in antialiasing, but not much else.
+const TOLERANCE: u8 = 2;
+
+static INIT: Once = Once::new();
+
+fn snapshot_dir() -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests")
+        .join("snapshots")
+}
+
+fn init_config() {
+    INIT.call_once(|| {
+        let config =
+            Config::load_file(snapshot_dir().join("config.ron")).expect("Couldn't load snapshot config.");
+        Config::set(config);
+    });
+}
+
+fn notification(summary: &str, body: &str) -> Notification {
+    Notification::from_self(summary, body, Timeout::Default)
+}
+
+// A small, easily recognizable image: a red to blue gradient, with a green diagonal.
+fn test_image() -> ImageData {
+    let image = RgbaImage::from_fn(16, 16, |x, y| {
+        if x == y {
+            Rgba([0, 255, 0, 255])
+        } else {
+            Rgba([(x * 16) as u8, 0, (y * 16) as u8, 255])
+        }
+    });
+
+    ImageData::Dynamic(DynamicImage::ImageRgba8(image))
+}
+
+// Text is compared pixel for pixel, so a missing font would just look like every snapshot with
+// text in it had changed.  Catch that up front instead.
+fn check_fonts(layout: &mut LayoutBlock) {
+    for (_, font) in layout.params.fonts_mut() {
+        if let Err(e) = text::check_font(font) {
+            panic!(
+                "Layout \"{}\": {}  The snapshots can't match without it, so install it first.",
+                layout.name, e
+            );
+        }
+    }
+
+    for child in &mut layout.children {
+        check_fonts(child);
+    }
+}
+
+// Draw `notification` with the layout called `name`, and check it against `<name>.png`.
+fn assert_snapshot(name: &str, notification: Notification) {
+    assert_snapshot_as(name, name, notification);
+}
+
+fn assert_snapshot_as(snapshot: &str, layout_name: &str, notification: Notification) {
+    assert_snapshot_after(snapshot, layout_name, notification, 0);
+}
+
+// Like `assert_snapshot_as`, but drawn `elapsed` milliseconds after the notification spawned.
+fn assert_snapshot_after(snapshot: &str, layout_name: &str, notification: Notification, elapsed: i32) {
+    init_config();
+
+    let mut layout = Config::get()
+        .layouts
+        .iter()
+        .find(|l| l.name == layout_name)
+        .unwrap_or_else(|| panic!("No layout named {} in the snapshot config.", layout_name))
+        .clone();
+    check_fonts(&mut layout);
+
+    let mut render = OffscreenRender::new(notification, 1.0);
+    render.set_elapsed(elapsed);
+    let rect = render.render(&mut layout);
+    let mut image = render.into_image();
+
+    // What's predicted should be exactly what we make room for.
+    assert_eq!(
+        (rect.width().ceil() as i32, rect.height().ceil() as i32),
+        (image.width(), image.height()),
+        "{}: predicted rect {:?} doesn't match the drawn size.",
+        snapshot,
+        rect,
+    );
+
+    let path = snapshot_dir().join(format!("{}.png", snapshot));
+    if std::env::var_os("WIRED_UPDATE_SNAPSHOTS").is_some() {
+        let mut file = File::create(&path).expect("Couldn't create snapshot.");
+        image.write_to_png(&mut file).expect("Couldn't write snapshot.");
+        eprintln!("Wrote snapshot {}.", path.display());
+        return;
+    }
+
+    if !path.exists() {
+        panic!(
+            "{}: there's no reference at {}.  Run with WIRED_UPDATE_SNAPSHOTS=1 to write one.",
+            snapshot,
+            path.display()
+        );
+    }
+
+    let mut file = File::open(&path).expect("Couldn't open snapshot.");
+    let mut reference = ImageSurface::create_from_png(&mut file).expect("Couldn't read snapshot.");
+
+    let differences = if (reference.width(), reference.height()) != (image.width(), image.height()) {
+        Err(format!(
+            "size is {}x{}, but the reference is {}x{}",
+            image.width(),
+            image.height(),
+            reference.width(),
+            reference.height()
+        ))
+    } else {
+        let count = count_differences(&mut image, &mut reference);
+        if count == 0 {
+            Ok(())
+        } else {
+            Err(format!("{} pixel(s) differ from the reference", count))
+        }
+    };
+
+    if let Err(e) = differences {
+        let new_path = snapshot_dir().join(format!("{}.new.png", snapshot));
+        let mut file = File::create(&new_path).expect("Couldn't create snapshot.");
+        image.write_to_png(&mut file).expect("Couldn't write snapshot.");
+        panic!(
+            "{}: {}.  See {} for what was drawn.",
+            snapshot,
+            e,
+            new_path.display()
+        );
+    }
+}
+
+// How many pixels are further than `TOLERANCE` apart, for two images of the same size.
+fn count_differences(image: &mut ImageSurface, reference: &mut ImageSurface) -> usize {
+    let (width, height) = (image.width() as usize, image.height() as usize);
+    let (stride, ref_stride) = (image.stride() as usize, reference.stride() as usize);
+    let data = image.data().expect("Couldn't read drawn image.");
+    let ref_data = reference.data().expect("Couldn't read snapshot.");
+
+    let mut count = 0;
+    for y in 0..height {
+        let row = &data[y * stride..y * stride + width * 4];
+        let ref_row = &ref_data[y * ref_stride..y * ref_stride + width * 4];
+        for (pixel, ref_pixel) in row.chunks(4).zip(ref_row.chunks(4)) {
+            if pixel
+                .iter()
+                .zip(ref_pixel)
+                .any(|(a, b)| a.abs_diff(*b) > TOLERANCE)
+            {
+                count += 1;
+            }
+        }
+    }
+
+    count
+}
+
+#[test]
+fn notification_block() {
+    assert_snapshot("notification", notification("Summary", ""));
+}
+
+#[test]
+fn notification_block_critical() {
+    let mut n = notification("Summary", "");
+    n.urgency = Urgency::Critical;
+    assert_snapshot_as("notification_critical", "notification", n);
+}
+
+#[test]
+fn notification_block_gradients() {
+    assert_snapshot("notification_gradient", notification("Gradients", ""));
+}
+
+#[test]
+fn notification_block_gradients_critical() {
+    // Urgency colors take over from `border_fill`.
+    let mut n = notification("Gradients", "");
+    n.urgency = Urgency::Critical;
+    assert_snapshot_as("notification_gradient_critical", "notification_gradient", n);
+}
+
+#[test]
+fn notification_block_image() {
+    assert_snapshot("notification_image", notification("Tiled image", ""));
+}
+
+#[test]
+fn notification_block_sides_and_corners() {
+    let mut n = notification("Docked", "");
+    n.urgency = Urgency::Normal;
+    assert_snapshot("notification_sides", n);
+}
+
+#[test]
+fn notification_block_urgency_overrides() {
+    let mut n = notification("Overridden", "");
+    n.urgency = Urgency::Normal;
+    assert_snapshot("notification_urgency", n);
+}
+
+#[test]
+fn notification_block_urgency_overrides_critical() {
+    let mut n = notification("Overridden", "");
+    n.urgency = Urgency::Critical;
+    assert_snapshot_as("notification_urgency_critical", "notification_urgency", n);
+}
+
+#[test]
+fn text_block() {
+    assert_snapshot("text", notification("Summary", "A short body."));
+}
+
+#[test]
+fn text_block_wrapped_and_ellipsized() {
+    let body = "A body that is much too long to fit on one line, so it has to wrap onto the next \
+                few lines, and then it runs out of room altogether and gets ellipsized at the end.";
+    assert_snapshot_as("text_long", "text", notification("Summary", body));
+}
+
+#[test]
+fn text_block_markup() {
+    let body = "Some <i>italic</i>, <u>underlined</u> and &amp; escaped text.";
+    assert_snapshot_as("text_markup", "text", notification("Summary", body));
+}
+
+#[test]
+fn text_block_paragraph() {
+    let body = "  Blank lines get collapsed,   \n\n\n\nand long URLs like https://example.com/a/very/long/path/to/somewhere \
+                wrap anywhere, with the lines justified and spaced out, until there are too many.";
+    assert_snapshot("text_paragraph", notification("", body));
+}
+
+#[test]
+fn text_block_shadow_and_outline() {
+    assert_snapshot("text_effects", notification("Shadowed", ""));
+}
+
+#[test]
+fn scrolling_text_block() {
+    assert_snapshot("scrolling_text", notification("", "Short."));
+}
+
+#[test]
+fn scrolling_text_block_overflowing() {
+    let body = "This body is far too long for the block, so it will scroll back and forth.";
+    assert_snapshot_as("scrolling_text_long", "scrolling_text", notification("", body));
+}
+
+#[test]
+fn image_block() {
+    let mut n = notification("Summary", "");
+    n.hint_image = Some(test_image());
+    assert_snapshot("image", n);
+}
+
+#[test]
+fn image_block_without_image() {
+    assert_snapshot_as("image_missing", "image", notification("Summary", ""));
+}
+
+#[test]
+fn button_block() {
+    let mut n = notification("Summary", "");
+    n.actions.insert("default".to_owned(), "Open".to_owned());
+    assert_snapshot("button", n);
+}
+
+#[test]
+fn button_block_sides_and_corners() {
+    let mut n = notification("Summary", "");
+    n.actions.insert("default".to_owned(), "Open".to_owned());
+    assert_snapshot("button_corners", n);
+}
+
+#[test]
+fn progress_block() {
+    let mut n = notification("Summary", "");
+    n.percentage = Some(0.4);
+    assert_snapshot("progress", n);
+}
+
+#[test]
+fn progress_block_full() {
+    let mut n = notification("Summary", "");
+    n.percentage = Some(1.0);
+    assert_snapshot_as("progress_full", "progress", n);
+}
+
+#[test]
+fn progress_block_sides_and_corners() {
+    let mut n = notification("Summary", "");
+    n.percentage = Some(0.6);
+    assert_snapshot("progress_corners", n);
+}
+
+#[test]
+fn timeout_block() {
+    let n = Notification::from_self("Summary", "", Timeout::Milliseconds(10000));
+    assert_snapshot_after("timeout", "timeout", n, 5000);
+}
+
+#[test]
+fn timeout_block_full() {
+    let n = Notification::from_self("Summary", "", Timeout::Milliseconds(10000));
+    assert_snapshot_as("timeout_full", "timeout", n);
+}
+
+#[test]
+fn timeout_block_ring() {
+    let n = Notification::from_self("Summary", "", Timeout::Milliseconds(10000));
+    assert_snapshot_after("timeout_ring", "timeout_ring", n, 5000);
+}
@@ -1,4 +1,4 @@
-use pango::{self, FontDescription, Layout};
+use pango::{self, prelude::*, FontDescription, Layout};
 
 use serde::Deserialize;
 
@@ -53,6 +53,190 @@ impl AlignMode {
     }
 }
 
+// Where lines are allowed to break when text wraps.
+#[derive(Debug, Deserialize, Clone, Default)]
+pub enum WrapMode {
+    // Between words only, so long words (e.g. URLs) can stick out past the edge.
+    Word,
+    // Anywhere.
+    Char,
+    // Between words, falling back to anywhere for words that don't fit on a line.
+    #[default]
+    WordChar,
+}
+
+impl WrapMode {
+    pub fn to_pango_mode(&self) -> pango::WrapMode {
+        match self {
+            WrapMode::Word => pango::WrapMode::Word,
+            WrapMode::Char => pango::WrapMode::Char,
+            WrapMode::WordChar => pango::WrapMode::WordChar,
+        }
+    }
+}
+
+// A drop shadow behind text, to help it stand out from whatever is behind it.
+#[derive(Debug, Deserialize, Clone)]
+pub struct TextShadow {
+    pub color: Color,
+    pub offset: Vec2,
+    // Roughly how far the shadow is spread out, in pixels.  0 for a hard shadow.
+    #[serde(default)]
+    pub blur: f64,
+}
+
+impl TextShadow {
+    // Where copies of the text are drawn to fake a blur: a grid spread over the blur radius,
+    // rounded off into a circle.
+    fn blur_offsets(&self) -> Vec<(f64, f64)> {
+        if self.blur <= 0.0 {
+            return vec![(0.0, 0.0)];
+        }
+
+        let step = self.blur / 2.0;
+        let mut offsets = vec![];
+        for i in -2..=2 {
+            for j in -2..=2 {
+                if i * i + j * j <= 5 {
+                    offsets.push((i as f64 * step, j as f64 * step));
+                }
+            }
+        }
+
+        offsets
+    }
+}
+
+// A line around the outside of text.
+#[derive(Debug, Deserialize, Clone)]
+pub struct TextOutline {
+    pub color: Color,
+    pub width: f64,
+}
+
+// Steps for cleaning up text.  A list of these is applied in order, to the summary and body of
+// every notification as it arrives (`text_normalization`), and to the text of a `TextBlock`
+// (`normalize`).
+#[derive(Debug, Deserialize, Clone, PartialEq)]
+pub enum TextNormalization {
+    // Remove whitespace from the start and end.
+    Trim,
+    // Remove whitespace from the start and end of every line.
+    TrimLines,
+    // Turn runs of spaces and tabs into a single space.
+    CollapseSpaces,
+    // Turn runs of blank lines into a single blank line.
+    CollapseBlankLines,
+    // Remove blank lines altogether.
+    RemoveBlankLines,
+    // Put everything on one line, separating the lines with spaces.
+    JoinLines,
+}
+
+impl TextNormalization {
+    pub fn apply(&self, text: &str) -> String {
+        match self {
+            TextNormalization::Trim => text.trim().to_owned(),
+            TextNormalization::TrimLines => text.lines().map(str::trim).collect::<Vec<_>>().join("\n"),
+            TextNormalization::CollapseSpaces => {
+                let mut collapsed = String::with_capacity(text.len());
+                let mut last_was_space = false;
+                for c in text.chars() {
+                    let is_space = c == ' ' || c == '\t';
+                    if !(is_space && last_was_space) {
+                        collapsed.push(if is_space { ' ' } else { c });
+                    }
+                    last_was_space = is_space;
+                }
+                collapsed
+            }
+            TextNormalization::CollapseBlankLines => {
+                let mut lines: Vec<&str> = Vec::new();
+                for line in text.lines() {
+                    let blank = line.trim().is_empty();
+                    // Blank lines are all pushed as empty, so that's all we need to check for.
+                    if !(blank && lines.last() == Some(&"")) {
+                        lines.push(if blank { "" } else { line });
+                    }
+                }
+                lines.join("\n")
+            }
+            TextNormalization::RemoveBlankLines => text
+                .lines()
+                .filter(|l| !l.trim().is_empty())
+                .collect::<Vec<_>>()
+                .join("\n"),
+            TextNormalization::JoinLines => text.lines().collect::<Vec<_>>().join(" "),
+        }
+    }
+}
+
+// Apply each of `steps` to `text`, in order.
+pub fn normalize(text: &str, steps: &[TextNormalization]) -> String {
+    steps.iter().fold(text.to_owned(), |text, step| step.apply(&text))
+}
+
+// What `text` looks like once it's drawn: markup tags taken out, and entities decoded.  Text
+// that isn't valid markup is left as it is.
+pub fn plain_text(text: &str) -> String {
+    match pango::parse_markup(text, '\0') {
+        Ok((_, plain, _)) => plain.to_string(),
+        Err(_) => text.to_owned(),
+    }
+}
+
+// Families that fontconfig resolves to something real, even though no font is called that.
+static GENERIC_FAMILIES: [&str; 8] = [
+    "sans",
+    "sans-serif",
+    "serif",
+    "monospace",
+    "mono",
+    "cursive",
+    "fantasy",
+    "system-ui",
+];
+
+// Check that a font description has a size, and names a family we can actually find.
+// Pango will happily fall back to some other font, so mistakes here are easy to miss.
+pub fn check_font(font: &str) -> Result<(), String> {
+    let font_dsc = FontDescription::from_string(font);
+    if font_dsc.size() <= 0 {
+        return Err(format!("font \"{}\" doesn't specify a size.", font));
+    }
+
+    let family = match font_dsc.family() {
+        Some(f) => f.to_string(),
+        None => return Err(format!("font \"{}\" doesn't specify a family.", font)),
+    };
+
+    let installed: Vec<String> = match pangocairo::FontMap::default() {
+        Some(font_map) => font_map
+            .list_families()
+            .iter()
+            .filter_map(|f| f.name())
+            .map(|n| n.to_lowercase())
+            .collect(),
+        // Can't check, so assume it's fine.
+        None => return Ok(()),
+    };
+
+    // Families can be a comma separated list of fallbacks; any one of them is enough.
+    let found = family
+        .split(',')
+        .map(|f| f.trim().to_lowercase())
+        .any(|f| GENERIC_FAMILIES.contains(&f.as_str()) || installed.contains(&f));
+
+    if found {
+        Ok(())
+    } else {
+        Err(format!(
+            "couldn't find font family \"{}\" (from font \"{}\").",
+            family, font
+        ))
+    }
+}
+
 #[derive(Debug)]
 pub struct TextRenderer {
     //config: &'a Config,
@@ -70,6 +254,13 @@ impl TextRenderer {
         Self { pctx, layout }
     }
 
+    // Pick up the font options and resolution of a new cairo context, for when we start drawing to
+    // a different surface.
+    pub fn update_context(&self, ctx: &cairo::Context) {
+        pangocairo::functions::update_context(ctx, &self.pctx);
+        self.layout.context_changed();
+    }
+
     // Sets the current text of the renderer, applying markup and ellipsizing according to
     // ellipsize mode and `max_width` / `max_height`.
     pub fn set_text(
@@ -102,6 +293,31 @@ impl TextRenderer {
         self.layout.set_markup(text);
         self.layout.set_height(height);
         self.layout.set_width(width);
+
+        // The layout is shared between blocks, so undo anything `set_paragraph` did for the last
+        // one.
+        self.layout.set_wrap(pango::WrapMode::WordChar);
+        self.layout.set_spacing(0);
+        self.layout.set_justify(false);
+    }
+
+    // Changes how the current text is broken into lines and spaced out.  `set_text` resets all of
+    // this, so it has to be called after it.
+    // `max_lines` only cuts text short when it's being ellipsized, like `max_height`.  Whichever
+    // of the two fits fewer lines wins.
+    pub fn set_paragraph(&self, wrap: &WrapMode, line_spacing: f64, justify: bool, max_lines: Option<u32>) {
+        self.layout.set_wrap(wrap.to_pango_mode());
+        self.layout
+            .set_spacing((line_spacing * pango::SCALE as f64) as i32);
+        self.layout.set_justify(justify);
+
+        if let Some(lines) = max_lines {
+            let lines = lines.max(1) as i32;
+            if self.layout.line_count() > lines {
+                // Negative heights are a number of lines, rather than pixels.
+                self.layout.set_height(-lines);
+            }
+        }
     }
 
     // Gets a raw, unpadded rect which surrounds the text.
@@ -132,19 +348,95 @@ impl TextRenderer {
         rect
     }
 
-    // Paints current text at the specified position in the specified color.
-    pub fn paint(&self, ctx: &cairo::Context, pos: &Vec2, color: &Color) {
+    // Paints current text at the specified position in the specified color, with an optional
+    // shadow and outline.
+    // Neither the shadow nor the outline are included in the text's rect, so they can stick out
+    // into the padding.
+    pub fn paint(
+        &self,
+        ctx: &cairo::Context,
+        pos: &Vec2,
+        color: &Color,
+        shadow: Option<&TextShadow>,
+        outline: Option<&TextOutline>,
+    ) -> Result<(), cairo::Error> {
+        let outline_width = outline.map_or(0.0, |o| o.width);
+
+        if let Some(shadow) = shadow {
+            // Each copy is drawn separately and then faded, so that the copies (and their
+            // outlines) build up the shadow's color where they overlap, rather than where they
+            // don't.
+            let offsets = shadow.blur_offsets();
+            let alpha = 1.0 - (1.0 - shadow.color.a).powf(1.0 / offsets.len() as f64);
+            let c = &shadow.color;
+            for (x, y) in offsets {
+                ctx.push_group();
+                ctx.set_source_rgb(c.r, c.g, c.b);
+                ctx.move_to(pos.x + shadow.offset.x + x, pos.y + shadow.offset.y + y);
+                self.fill_and_stroke(ctx, outline_width)?;
+                ctx.pop_group_to_source()?;
+                ctx.paint_with_alpha(alpha)?;
+            }
+        }
+
+        if let Some(outline) = outline {
+            // The inside half of the stroke is covered up by the text.
+            let c = &outline.color;
+            ctx.set_source_rgba(c.r, c.g, c.b, c.a);
+            ctx.move_to(pos.x, pos.y);
+            pangocairo::functions::layout_path(ctx, &self.layout);
+            ctx.set_line_width(outline.width * 2.0);
+            ctx.set_line_join(cairo::LineJoin::Round);
+            ctx.stroke()?;
+        }
+
         // Move cursor to draw position and draw text.
         ctx.set_source_rgba(color.r, color.g, color.b, color.a);
         ctx.move_to(pos.x, pos.y);
         pangocairo::functions::show_layout(ctx, &self.layout);
+        Ok(())
     }
 
     // Paints current text at the specified position, offsetting for the provided padding.
-    pub fn paint_padded(&self, ctx: &cairo::Context, pos: &Vec2, color: &Color, padding: &Padding) {
+    pub fn paint_padded(
+        &self,
+        ctx: &cairo::Context,
+        pos: &Vec2,
+        color: &Color,
+        padding: &Padding,
+        shadow: Option<&TextShadow>,
+        outline: Option<&TextOutline>,
+    ) -> Result<(), cairo::Error> {
         // Text rendered within padded rects need to be moved to the padded position before
         // drawing.
         let pos = Vec2::new(pos.x + padding.left, pos.y + padding.top);
-        self.paint(ctx, &pos, color);
+        self.paint(ctx, &pos, color, shadow, outline)
+    }
+
+    // Fills the text at the current point with the current source, with an outline
+    // `outline_width` wide if it's more than 0.
+    fn fill_and_stroke(&self, ctx: &cairo::Context, outline_width: f64) -> Result<(), cairo::Error> {
+        pangocairo::functions::layout_path(ctx, &self.layout);
+        if outline_width > 0.0 {
+            ctx.set_line_width(outline_width * 2.0);
+            ctx.set_line_join(cairo::LineJoin::Round);
+            ctx.stroke_preserve()?;
+        }
+        ctx.fill()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::plain_text;
+
+    #[test]
+    fn plain_text_strips_markup() {
+        assert_eq!(
+            plain_text("Some <b>bold</b>, <i>italic</i> &amp; escaped&#33;"),
+            "Some bold, italic & escaped!"
+        );
+        // Invalid markup is left alone.
+        assert_eq!(plain_text("Not <b>closed"), "Not <b>closed");
     }
 }
@@ -0,0 +1,531 @@
+// A native Wayland backend, which uses the wlr layer shell protocol (sway, Hyprland, river, etc.)
+// to put notifications on screen, so that we don't need XWayland.
+// Layouts are drawn into cairo image surfaces exactly like on X11, and then copied into shared
+// memory buffers for the compositor.
+//
+// Events come in on our own Wayland connection rather than through winit, so the manager has to
+// `dispatch` them every update.  Pointer events are turned into winit `WindowEvent`s so that they
+// go through the same handling as X11's.
+
+use std::io::ErrorKind;
+
+use cairo::{Format, ImageSurface};
+use smithay_client_toolkit::{
+    compositor::{CompositorHandler, CompositorState, Region},
+    delegate_compositor, delegate_layer, delegate_output, delegate_pointer, delegate_registry, delegate_seat,
+    delegate_shm,
+    output::{OutputHandler, OutputState},
+    reexports::client::{
+        backend::WaylandError,
+        globals::registry_queue_init,
+        protocol::{wl_output, wl_pointer, wl_seat, wl_shm, wl_surface},
+        Connection, EventQueue, Proxy, QueueHandle,
+    },
+    registry::{ProvidesRegistryState, RegistryState},
+    registry_handlers,
+    seat::{
+        pointer::{PointerEvent, PointerEventKind, PointerHandler},
+        Capability, SeatHandler, SeatState,
+    },
+    shell::{
+        wlr_layer::{
+            Anchor, KeyboardInteractivity, Layer, LayerShell, LayerShellHandler, LayerSurface,
+            LayerSurfaceConfigure,
+        },
+        WaylandSurface,
+    },
+    shm::{
+        slot::{Buffer, SlotPool},
+        Shm, ShmHandler,
+    },
+};
+use winit::{
+    dpi::PhysicalPosition,
+    event::{DeviceId, ElementState, MouseButton, WindowEvent},
+    monitor::MonitorHandle,
+    window::WindowId,
+};
+
+use crate::config::{Backend, Config};
+use crate::maths_utility::Vec2;
+
+// Linux input event codes for mouse buttons, from `linux/input-event-codes.h`.
+const BTN_LEFT: u32 = 0x110;
+const BTN_RIGHT: u32 = 0x111;
+const BTN_MIDDLE: u32 = 0x112;
+const BTN_SIDE: u32 = 0x113;
+const BTN_EXTRA: u32 = 0x114;
+
+pub enum WaylandEvent {
+    // The compositor is ready for the window to be drawn.
+    Configured(WindowId),
+    // The compositor took the window away, e.g. because its output was unplugged.
+    Closed(WindowId),
+    Input(WindowId, WindowEvent),
+}
+
+pub struct WaylandBackend {
+    event_queue: EventQueue<WaylandState>,
+    state: WaylandState,
+}
+
+struct WaylandState {
+    registry_state: RegistryState,
+    output_state: OutputState,
+    seat_state: SeatState,
+    compositor: CompositorState,
+    layer_shell: LayerShell,
+    shm: Shm,
+    pointer: Option<wl_pointer::WlPointer>,
+
+    // Handed over to the manager on the next `dispatch`.
+    events: Vec<WaylandEvent>,
+}
+
+impl WaylandBackend {
+    // Connect to the compositor, if the config says to use Wayland.
+    // With `Backend::Auto` we only try if there's a Wayland session, and quietly fall back to X11
+    // (which will be XWayland) if the compositor doesn't support the layer shell.
+    pub fn init() -> Option<Self> {
+        match Config::get().backend {
+            Backend::X11 => None,
+            Backend::Wayland => match WaylandBackend::connect() {
+                Ok(backend) => Some(backend),
+                Err(e) => {
+                    eprintln!("Couldn't use the Wayland backend: {}", e);
+                    std::process::exit(1);
+                }
+            },
+            Backend::Auto => {
+                std::env::var("WAYLAND_DISPLAY").ok()?;
+                match WaylandBackend::connect() {
+                    Ok(backend) => Some(backend),
+                    Err(e) => {
+                        eprintln!("Couldn't use the Wayland backend, falling back to X11: {}", e);
+                        None
+                    }
+                }
+            }
+        }
+    }
+
+    fn connect() -> Result<Self, String> {
+        let conn =
+            Connection::connect_to_env().map_err(|e| format!("couldn't connect to the compositor: {}", e))?;
+        let (globals, mut event_queue) = registry_queue_init::<WaylandState>(&conn)
+            .map_err(|e| format!("couldn't list globals: {}", e))?;
+        let qh = event_queue.handle();
+
+        let compositor = CompositorState::bind(&globals, &qh)
+            .map_err(|e| format!("wl_compositor isn't available: {}", e))?;
+        let layer_shell = LayerShell::bind(&globals, &qh)
+            .map_err(|_| "the compositor doesn't support the wlr layer shell protocol.".to_owned())?;
+        let shm = Shm::bind(&globals, &qh).map_err(|e| format!("wl_shm isn't available: {}", e))?;
+
+        let mut state = WaylandState {
+            registry_state: RegistryState::new(&globals),
+            output_state: OutputState::new(&globals, &qh),
+            seat_state: SeatState::new(&globals, &qh),
+            compositor,
+            layer_shell,
+            shm,
+            pointer: None,
+            events: vec![],
+        };
+
+        // Get the outputs' details now, so that windows can be put on the right one straight away.
+        event_queue
+            .roundtrip(&mut state)
+            .map_err(|e| format!("couldn't get output details: {}", e))?;
+
+        Ok(Self { event_queue, state })
+    }
+
+    // Create a layer surface for a window, on `monitor`'s output.
+    // `width` and `height` are in logical pixels.
+    pub fn create_window(
+        &self,
+        width: f64,
+        height: f64,
+        scale: f64,
+        monitor: Option<&MonitorHandle>,
+    ) -> LayerWindow {
+        let qh = self.event_queue.handle();
+        let state = &self.state;
+
+        // Match winit's monitor up with our own connection's output by name.
+        let name = monitor.and_then(|m| m.name());
+        let output = state.output_state.outputs().find(|o| {
+            let info = state.output_state.info(o);
+            name.is_some() && info.and_then(|i| i.name) == name
+        });
+
+        let surface = state.compositor.create_surface(&qh);
+        let layer = state.layer_shell.create_layer_surface(
+            &qh,
+            surface,
+            Layer::Overlay,
+            Some("wired"),
+            output.as_ref(),
+        );
+        // We position notifications ourselves, as margins from the top left of the output.
+        layer.set_anchor(Anchor::TOP | Anchor::LEFT);
+        // Don't get pushed around by panels, and don't push anything else around either.
+        layer.set_exclusive_zone(-1);
+        layer.set_keyboard_interactivity(KeyboardInteractivity::None);
+
+        let (origin, output_scale) = match monitor {
+            Some(m) => (
+                Vec2::new(m.position().x.into(), m.position().y.into()),
+                m.scale_factor(),
+            ),
+            None => (Vec2::default(), 1.0),
+        };
+
+        // The pool grows as needed, so this is just a starting point.
+        let len = (width * height * scale * scale * 4.0) as usize;
+        let pool = SlotPool::new(len.max(4), &state.shm).expect("Failed to create Wayland buffer pool.");
+        let no_input = Region::new(&state.compositor).expect("Failed to create Wayland region.");
+
+        let mut window = LayerWindow {
+            layer,
+            pool,
+            buffer: None,
+            no_input,
+            image: ImageSurface::create(Format::ARgb32, 1, 1).expect("Failed to create image surface."),
+            buffer_scale: buffer_scale(scale),
+            configured: false,
+            origin,
+            output_scale,
+        };
+        window.resize(width, height);
+        window
+    }
+
+    // Read and handle any events from the compositor, without blocking.
+    pub fn dispatch(&mut self) -> Vec<WaylandEvent> {
+        self.flush();
+        if let Some(guard) = self.event_queue.prepare_read() {
+            match guard.read() {
+                Ok(_) => (),
+                Err(WaylandError::Io(e)) if e.kind() == ErrorKind::WouldBlock => (),
+                Err(e) => eprintln!("Error reading Wayland events: {}", e),
+            }
+        }
+
+        if let Err(e) = self.event_queue.dispatch_pending(&mut self.state) {
+            eprintln!("Error handling Wayland events: {}", e);
+        }
+
+        std::mem::take(&mut self.state.events)
+    }
+
+    // Send anything we've done to the compositor.
+    pub fn flush(&self) {
+        if let Err(e) = self.event_queue.flush() {
+            eprintln!("Error sending Wayland requests: {}", e);
+        }
+    }
+}
+
+// Buffers can only be scaled by whole numbers, so for fractional scales we draw a bit bigger and
+// let the compositor scale us down.
+fn buffer_scale(scale: f64) -> i32 {
+    (scale.ceil() as i32).max(1)
+}
+
+// Layer surfaces don't have a winit `WindowId`, so we make one from the surface.
+// The high bit keeps it clear of winit's ids, which come from a different connection.
+fn window_id(surface: &wl_surface::WlSurface) -> WindowId {
+    WindowId::from((1 << 63) | u64::from(surface.id().protocol_id()))
+}
+
+#[derive(Debug)]
+pub struct LayerWindow {
+    layer: LayerSurface,
+    pool: SlotPool,
+    // The compositor may still be reading from the last buffer we gave it, so it has to live until
+    // it's replaced.
+    buffer: Option<Buffer>,
+    // Left empty, for letting every click through.
+    no_input: Region,
+    image: ImageSurface,
+    buffer_scale: i32,
+    // We can't attach anything until the compositor has sent the first configure.
+    configured: bool,
+    // Where our output is, in physical pixels like the positions we're given.
+    origin: Vec2,
+    output_scale: f64,
+}
+
+impl LayerWindow {
+    pub fn id(&self) -> WindowId {
+        window_id(self.layer.wl_surface())
+    }
+
+    pub fn image(&self) -> ImageSurface {
+        self.image.clone()
+    }
+
+    pub fn configure(&mut self) {
+        self.configured = true;
+    }
+
+    // Positions are global and in physical pixels, and we're positioned with logical margins from
+    // the top left of our output.
+    pub fn set_position(&self, x: f64, y: f64) {
+        let left = ((x - self.origin.x) / self.output_scale).round() as i32;
+        let top = ((y - self.origin.y) / self.output_scale).round() as i32;
+        self.layer.set_margin(top, 0, 0, left);
+        self.layer.commit();
+    }
+
+    // Takes effect on the next commit.
+    pub fn set_click_through(&self, click_through: bool) {
+        let region = click_through.then(|| self.no_input.wl_region());
+        self.layer.set_input_region(region);
+    }
+
+    pub fn set_scale(&mut self, scale: f64) {
+        self.buffer_scale = buffer_scale(scale);
+    }
+
+    pub fn logical_size(&self) -> (f64, f64) {
+        let scale = self.buffer_scale as f64;
+        (
+            self.image.width() as f64 / scale,
+            self.image.height() as f64 / scale,
+        )
+    }
+
+    // Resize to a size in logical pixels, and hand back a new image to draw into at that size.
+    // Buffers have to be a whole multiple of the buffer scale, so we round up to whole logical
+    // pixels first.
+    pub fn resize(&mut self, width: f64, height: f64) -> ImageSurface {
+        let (width, height) = (width.ceil().max(1.0) as i32, height.ceil().max(1.0) as i32);
+        self.layer.set_size(width as u32, height as u32);
+        self.layer.commit();
+
+        let scale = self.buffer_scale;
+        self.image = ImageSurface::create(Format::ARgb32, width * scale, height * scale)
+            .expect("Failed to create image surface.");
+        self.image.set_device_scale(scale as f64, scale as f64);
+        self.image.clone()
+    }
+
+    // Copy what's been drawn over to the compositor.
+    pub fn present(&mut self) {
+        if !self.configured {
+            return;
+        }
+
+        let (width, height, stride) = (self.image.width(), self.image.height(), self.image.stride());
+        let (buffer, canvas) = match self
+            .pool
+            .create_buffer(width, height, stride, wl_shm::Format::Argb8888)
+        {
+            Ok(b) => b,
+            Err(e) => {
+                eprintln!("Couldn't create Wayland buffer: {}", e);
+                return;
+            }
+        };
+
+        // Cairo's ARGB32 is the same premultiplied, native endian format as wl_shm's ARGB8888.
+        if let Err(e) = self.image.with_data(|data| canvas.copy_from_slice(data)) {
+            eprintln!("Couldn't read back drawn image: {}", e);
+            return;
+        }
+
+        let surface = self.layer.wl_surface();
+        if let Err(e) = buffer.attach_to(surface) {
+            eprintln!("Couldn't attach Wayland buffer: {:?}", e);
+            return;
+        }
+        surface.set_buffer_scale(self.buffer_scale);
+        surface.damage_buffer(0, 0, width, height);
+        self.layer.commit();
+        self.buffer = Some(buffer);
+    }
+}
+
+impl CompositorHandler for WaylandState {
+    // We pick our scale from winit's monitors, like on X11.
+    fn scale_factor_changed(
+        &mut self,
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+        _surface: &wl_surface::WlSurface,
+        _new_factor: i32,
+    ) {
+    }
+
+    fn transform_changed(
+        &mut self,
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+        _surface: &wl_surface::WlSurface,
+        _new_transform: wl_output::Transform,
+    ) {
+    }
+
+    // We draw on our own schedule, so we never ask for frame callbacks.
+    fn frame(
+        &mut self,
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+        _surface: &wl_surface::WlSurface,
+        _time: u32,
+    ) {
+    }
+}
+
+impl OutputHandler for WaylandState {
+    fn output_state(&mut self) -> &mut OutputState {
+        &mut self.output_state
+    }
+
+    fn new_output(&mut self, _conn: &Connection, _qh: &QueueHandle<Self>, _output: wl_output::WlOutput) {}
+
+    fn update_output(&mut self, _conn: &Connection, _qh: &QueueHandle<Self>, _output: wl_output::WlOutput) {}
+
+    fn output_destroyed(
+        &mut self,
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+        _output: wl_output::WlOutput,
+    ) {
+    }
+}
+
+impl LayerShellHandler for WaylandState {
+    fn closed(&mut self, _conn: &Connection, _qh: &QueueHandle<Self>, layer: &LayerSurface) {
+        self.events
+            .push(WaylandEvent::Closed(window_id(layer.wl_surface())));
+    }
+
+    fn configure(
+        &mut self,
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+        layer: &LayerSurface,
+        _configure: LayerSurfaceConfigure,
+        _serial: u32,
+    ) {
+        // We always ask for a size, so there's nothing to take from the configure itself.
+        self.events
+            .push(WaylandEvent::Configured(window_id(layer.wl_surface())));
+    }
+}
+
+impl SeatHandler for WaylandState {
+    fn seat_state(&mut self) -> &mut SeatState {
+        &mut self.seat_state
+    }
+
+    fn new_seat(&mut self, _conn: &Connection, _qh: &QueueHandle<Self>, _seat: wl_seat::WlSeat) {}
+
+    fn new_capability(
+        &mut self,
+        _conn: &Connection,
+        qh: &QueueHandle<Self>,
+        seat: wl_seat::WlSeat,
+        capability: Capability,
+    ) {
+        if capability == Capability::Pointer && self.pointer.is_none() {
+            match self.seat_state.get_pointer(qh, &seat) {
+                Ok(pointer) => self.pointer = Some(pointer),
+                Err(e) => eprintln!("Couldn't get Wayland pointer: {}", e),
+            }
+        }
+    }
+
+    fn remove_capability(
+        &mut self,
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+        _seat: wl_seat::WlSeat,
+        capability: Capability,
+    ) {
+        if capability == Capability::Pointer {
+            if let Some(pointer) = self.pointer.take() {
+                pointer.release();
+            }
+        }
+    }
+
+    fn remove_seat(&mut self, _conn: &Connection, _qh: &QueueHandle<Self>, _seat: wl_seat::WlSeat) {}
+}
+
+impl PointerHandler for WaylandState {
+    fn pointer_frame(
+        &mut self,
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+        _pointer: &wl_pointer::WlPointer,
+        events: &[PointerEvent],
+    ) {
+        // Nothing looks at the device, and there's no winit device for our own connection anyway.
+        let device_id = unsafe { DeviceId::dummy() };
+
+        for event in events {
+            let id = window_id(&event.surface);
+            // Logical pixels; the manager scales these up to match what winit gives us on X11.
+            let position = PhysicalPosition::new(event.position.0, event.position.1);
+
+            let window_event = match event.kind {
+                PointerEventKind::Enter { .. } | PointerEventKind::Motion { .. } => {
+                    WindowEvent::CursorMoved { device_id, position }
+                }
+                PointerEventKind::Leave { .. } => WindowEvent::CursorLeft { device_id },
+                PointerEventKind::Press { button, .. } => WindowEvent::MouseInput {
+                    device_id,
+                    state: ElementState::Pressed,
+                    button: to_mouse_button(button),
+                },
+                PointerEventKind::Release { button, .. } => WindowEvent::MouseInput {
+                    device_id,
+                    state: ElementState::Released,
+                    button: to_mouse_button(button),
+                },
+                PointerEventKind::Axis { .. } => continue,
+            };
+
+            self.events.push(WaylandEvent::Input(id, window_event));
+        }
+    }
+}
+
+// The same mapping winit uses for its own Wayland windows.
+fn to_mouse_button(button: u32) -> MouseButton {
+    match button {
+        BTN_LEFT => MouseButton::Left,
+        BTN_RIGHT => MouseButton::Right,
+        BTN_MIDDLE => MouseButton::Middle,
+        BTN_SIDE => MouseButton::Back,
+        BTN_EXTRA => MouseButton::Forward,
+        other => MouseButton::Other(other as u16),
+    }
+}
+
+impl ShmHandler for WaylandState {
+    fn shm_state(&mut self) -> &mut Shm {
+        &mut self.shm
+    }
+}
+
+impl ProvidesRegistryState for WaylandState {
+    fn registry(&mut self) -> &mut RegistryState {
+        &mut self.registry_state
+    }
+
+    registry_handlers![OutputState, SeatState];
+}
+
+delegate_compositor!(WaylandState);
+delegate_output!(WaylandState);
+delegate_shm!(WaylandState);
+delegate_seat!(WaylandState);
+delegate_pointer!(WaylandState);
+delegate_layer!(WaylandState);
+delegate_registry!(WaylandState);
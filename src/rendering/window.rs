@@ -3,8 +3,9 @@ use std::time::Duration;
 use winit::{
     dpi::{PhysicalPosition, PhysicalSize},
     event_loop::EventLoopWindowTarget,
+    monitor::MonitorHandle,
     platform::x11::{WindowBuilderExtX11, XWindowType},
-    window::{Window, WindowBuilder, WindowLevel},
+    window::{Window, WindowBuilder, WindowId, WindowLevel},
 };
 
 use raw_window_handle::{HasDisplayHandle, HasWindowHandle, RawDisplayHandle, RawWindowHandle};
@@ -35,12 +36,14 @@ pub fn get_xlib_window(window: &Window) -> Option<xlib::Window> {
 }
 
 use crate::{
-    bus::dbus::{Notification, Timeout},
-    config::Config,
+    bus::dbus::{Notification, Timeout, Urgency},
+    config::{Config, TimeoutPolicy},
     manager::NotifyWindowManager,
-    maths_utility::{Rect, Vec2},
+    maths_utility::{self, Rect, ShapeKind, Vec2},
+    rendering::context::RenderContext,
     rendering::layout::LayoutBlock,
-    rendering::text::TextRenderer,
+    rendering::text::{self, TextRenderer},
+    rendering::wayland::LayerWindow,
 };
 
 // FuseOnly probably won't be used, but it's here for completion's sake.
@@ -52,6 +55,31 @@ bitflags! {
     }
 }
 
+// What's actually on screen, depending on which backend we're using.
+#[derive(Debug)]
+pub enum WindowHandle {
+    X11(Window),
+    Wayland(LayerWindow),
+}
+
+// A slide and/or fade that's in progress.
+#[derive(Debug)]
+struct Animation {
+    from: Vec2,
+    to: Vec2,
+    from_opacity: f64,
+    to_opacity: f64,
+    // In milliseconds.
+    elapsed: f64,
+}
+
+// The shapes last set on a window, see `NotifyWindow::update_shape`.  `None` is the whole window.
+#[derive(Debug, Default, PartialEq)]
+struct WindowShape {
+    input: Option<Vec<Rect>>,
+    bounding: Option<Vec<Rect>>,
+}
+
 #[derive(Debug)]
 pub struct NotifyWindow {
     // Context/Surface are placed at the top (in order) so that they are dropped first when a
@@ -61,23 +89,43 @@ pub struct NotifyWindow {
     // Each window has a text renderer to handle all text rendering for that window.
     pub text: TextRenderer,
 
-    pub winit: Window,
+    pub handle: WindowHandle,
     pub notification: Notification,
 
     // Layout is cloned from config so each notification can have its own mutable copy.
     // This is pretty much just so we can change some params on LayoutBlocks, which is a bit
     // wasteful, but easy.
     pub layout: Option<LayoutBlock>,
+    // Which copy of the notification this is, for layouts that are mirrored across monitors.
+    pub mirror: usize,
 
     pub marked_for_destroy: bool,
     // Master offset is used to offset all *elements* when drawing.
     // It is useful when the notification expands in either left or top direction.
     pub master_offset: Vec2,
+    // Layouts are drawn in logical pixels, which are this many physical pixels on screen.  Comes
+    // from the monitor we're on, for HiDPI monitors.
+    pub scale: f64,
     pub fuse: Timeout,
+    // What the fuse started at, so we can tell how far through it we are.
+    pub fuse_total: Timeout,
+
+    // Where the window is on screen, once it has been positioned, and where it's headed.
+    position: Option<Vec2>,
+    target_position: Vec2,
+    // Applied to the whole window when drawing, for fading.
+    pub opacity: f64,
+    animation: Option<Animation>,
+    // Whether the window is animating out, see `begin_close`.
+    closing: bool,
 
     // `update_enabled` is primarily used for pause functionality right now.
     //pub update_enabled: bool,
     pub update_mode: UpdateModes,
+    // Whether the fuse is paused because the cursor is over us, see `pause_on_hover`.
+    hover_paused: bool,
+    // Counts down to the fuse carrying on after the cursor has left, in milliseconds.
+    hover_grace: Option<f64>,
 
     // Dirty state -- will be redrawn if this is true.
     pub dirty: bool,
@@ -88,14 +136,17 @@ pub struct NotifyWindow {
     last_mouse_pos: Vec2,
     // We cache the inner rect, since our windows don't resize, and X11 is slow to reply.
     cached_inner_rect: Option<Rect>,
+    shape: WindowShape,
 }
 
 impl NotifyWindow {
     pub fn new(
         el: &EventLoopWindowTarget<()>,
         notification: Notification,
-        mut layout: LayoutBlock,
+        layout: LayoutBlock,
         manager: &NotifyWindowManager,
+        monitor: Option<&MonitorHandle>,
+        scale: f64,
     ) -> Self {
         let cfg = Config::get();
         // The minimum window width and height is 1.0.  We need this size to generate an initial window.
@@ -104,86 +155,21 @@ impl NotifyWindow {
             (cfg.min_window_height as f64).max(1.0),
         );
 
-        // @NOTE: this is pretty messed up... It's annoying that winit only exposes a handle to the
-        // xlib display through an existing window, which means we have to use a dummy (hidden)
-        // window to grab it.
-        // We need the display to do `XMatchVisualInfo`, which we can't set after we've created the
-        // window.
-        // We might consider moving away from winit and just using xlib directly.  The only part
-        // we're really using at the moment is the event loop.
-        let xlib_display = get_xlib_display(&manager.base_window).expect("Couldn't get xlib_display.");
-
-        let visual_info = unsafe {
-            let mut vinfo = std::mem::MaybeUninit::<x11::xlib::XVisualInfo>::uninit();
-
-            let status = (x11::xlib::XMatchVisualInfo)(
-                xlib_display as _,
-                x11::xlib::XDefaultScreen(xlib_display as _) as i32,
-                32,
-                x11::xlib::TrueColor,
-                vinfo.as_mut_ptr(),
-            );
-
-            if status == 0 {
-                panic!("Couldn't get valid XVisualInfo.");
+        let (handle, surface) = match &manager.wayland {
+            Some(wayland) => {
+                let layer = wayland.create_window(width, height, scale, monitor);
+                let surface = (*layer.image()).clone();
+                (WindowHandle::Wayland(layer), surface)
+            }
+            None => {
+                let (winit, surface) = create_x11_window(el, manager, width, height, scale);
+                (WindowHandle::X11(winit), surface)
             }
-
-            vinfo.assume_init()
         };
 
-        // override_redirect bypasses the WM entirely on native X11, keeping us above everything.
-        // On XWayland (WAYLAND_DISPLAY is set) the compositor still controls stacking, so
-        // override_redirect breaks clicks and z-order instead.
-        let on_xwayland = std::env::var("WAYLAND_DISPLAY").is_ok();
-
-        let mut builder = WindowBuilder::new()
-            .with_inner_size(PhysicalSize { width, height })
-            .with_x11_window_type(vec![XWindowType::Notification, XWindowType::Utility])
-            .with_title("wired")
-            .with_x11_visual(visual_info.visualid as u32)
-            .with_transparent(true)
-            .with_decorations(false)
-            .with_window_level(WindowLevel::AlwaysOnTop)
-            // Spawn far off-screen instead of hidden — you can't draw to a hidden window.
-            .with_position(PhysicalPosition {
-                x: 999_999.0,
-                y: 999_999.0,
-            });
-
-        if !on_xwayland {
-            builder = builder.with_override_redirect(true);
-        }
-
-        let winit = builder.build(el).expect("Couldn't build winit window.");
-
-        // If these fail, it probably means we aren't on linux.
-        // In that case, we should fail before now however (`.with_x11_window_type()`).
-        let xlib_window =
-            get_xlib_window(&winit).expect("Couldn't get xlib window, make sure you're running X11.");
-
-        let surface = unsafe {
-            /*
-            let visual = x11::xlib::XDefaultVisual(
-                xlib_display as _,
-                0,
-            );
-            */
-
-            let sfc_raw = cairo_sys::cairo_xlib_surface_create(
-                xlib_display as _,
-                xlib_window,
-                visual_info.visual,
-                width as _,
-                height as _,
-            );
-
-            Surface::from_raw_full(sfc_raw)
-        }
-        .expect("Failed to create cairo surface.");
-
         let context = cairo::Context::new(&surface).expect("Failed to create cairo context.");
         let text = TextRenderer::new(&context);
-        let fuse = notification.timeout.clone();
+        let fuse = resolve_fuse(&notification, &layout);
 
         // If notifications should spawn paused, we check against threshold and against
         // `unpause_on_input`.
@@ -204,36 +190,33 @@ impl NotifyWindow {
             context,
             surface,
             text,
-            winit,
+            handle,
             notification,
             layout: None,
+            mirror: 0,
             marked_for_destroy: false,
             master_offset: Vec2::default(),
+            scale,
+            fuse_total: fuse.clone(),
             fuse,
+            position: None,
+            target_position: Vec2::default(),
+            opacity: 1.0,
+            animation: None,
+            closing: false,
             update_mode,
+            hover_paused: false,
+            hover_grace: None,
             dirty: true, // New windows are dirty -- no drawing has happened yet.
             creation_timestamp: Local::now(),
             last_mouse_pos: Vec2::new(0.0, 0.0),
             cached_inner_rect: None,
+            shape: WindowShape::default(),
         };
 
         // When we spawn a window, we get a `RedrawRequested` event which we draw from, so we don't
         // manually draw here.
-        // `Rect::new(0.0, 0.0, width, height) is basically the same as `window.get_inner_rect()`,
-        // but we don't trust it to be initialized yet.
-        let rect = layout.predict_rect_tree_and_init(
-            &window,
-            //&window.get_inner_rect(),
-            &Rect::new(0.0, 0.0, width, height), // This parameter is only used for positioning
-            Rect::new(0.0, 0.0, width, height),  // .. so we should also pass the min_size rect here
-                                                 // to ensure we don't get 0.0 width / 0.0 height.
-        );
-        let delta = Vec2::new(-rect.x(), -rect.y());
-
-        window.layout = Some(layout);
-        window.set_size(rect.width(), rect.height());
-        window.master_offset = delta;
-        window.cached_inner_rect = Some(Rect::new(0.0, 0.0, rect.width(), rect.height()));
+        window.fit_layout(layout);
         window
     }
 
@@ -244,32 +227,52 @@ impl NotifyWindow {
 
         // Refresh timeout if configured
         if cfg.replacing_resets_timeout {
-            self.fuse = self.notification.timeout.clone();
+            self.fuse = resolve_fuse(&self.notification, &new_layout);
+            self.fuse_total = self.fuse.clone();
         }
 
+        self.fit_layout(new_layout);
+        self.dirty = true;
+    }
+
+    // Moving to a monitor with a different scale factor means resizing the window to match.
+    pub fn set_scale(&mut self, scale: f64) {
+        self.scale = scale;
+        match &mut self.handle {
+            WindowHandle::X11(_) => self.surface.set_device_scale(scale, scale),
+            // The new image will be made at this scale when we resize below.
+            WindowHandle::Wayland(layer) => layer.set_scale(scale),
+        }
+        let layout = self.layout_take();
+        self.fit_layout(layout);
+        self.dirty = true;
+    }
+
+    // Initialize `layout` and resize the window to fit it.
+    fn fit_layout(&mut self, mut layout: LayoutBlock) {
+        let cfg = Config::get();
+        // The layout is a fresh copy from the config unless we're just rescaling, in which case the
+        // overrides are already there and applying them again changes nothing.
+        layout.apply_urgency_tree(&self.notification.urgency);
         // The minimum window width and height is 1.0.  We need this size to generate an initial window.
-        // TODO: merge the above function with this so we don't get regressions from not doing
-        // things the same way.
         let (width, height) = (
             (cfg.min_window_width as f64).max(1.0),
             (cfg.min_window_height as f64).max(1.0),
         );
 
-        // As above.  May be valuable to put this into a function like `prepare_notification` or
-        // something if we keep changing stuff.
-        let mut layout = new_layout;
+        // `Rect::new(0.0, 0.0, width, height) is basically the same as `window.get_inner_rect()`,
+        // but we don't trust it to be initialized yet.
         let rect = layout.predict_rect_tree_and_init(
             self,
-            &Rect::new(0.0, 0.0, width, height),
-            Rect::new(0.0, 0.0, width, height),
+            &Rect::new(0.0, 0.0, width, height), // This parameter is only used for positioning
+            Rect::new(0.0, 0.0, width, height),  // .. so we should also pass the min_size rect here
+                                                 // to ensure we don't get 0.0 width / 0.0 height.
         );
         let delta = Vec2::new(-rect.x(), -rect.y());
 
         self.layout = Some(layout);
         self.set_size(rect.width(), rect.height());
         self.master_offset = delta;
-        self.cached_inner_rect = Some(Rect::new(0.0, 0.0, rect.width(), rect.height()));
-        self.dirty = true;
     }
 
     pub fn _layout(&self) -> &LayoutBlock {
@@ -280,39 +283,217 @@ impl NotifyWindow {
         self.layout.take().unwrap()
     }
 
+    pub fn id(&self) -> WindowId {
+        match &self.handle {
+            WindowHandle::X11(winit) => winit.id(),
+            WindowHandle::Wayland(layer) => layer.id(),
+        }
+    }
+
+    // The compositor is ready for us to draw, on Wayland.
+    pub fn configured(&mut self) {
+        if let WindowHandle::Wayland(layer) = &mut self.handle {
+            layer.configure();
+            self.dirty = true;
+        }
+    }
+
     pub fn set_position(&self, x: f64, y: f64) {
-        self.winit.set_outer_position(PhysicalPosition { x, y });
+        match &self.handle {
+            WindowHandle::X11(winit) => winit.set_outer_position(PhysicalPosition { x, y }),
+            WindowHandle::Wayland(layer) => layer.set_position(x, y),
+        }
+    }
+
+    // Move to a new place in the stack, animating according to the config.
+    // The first move is the window's spawn.
+    pub fn move_to(&mut self, pos: Vec2) {
+        let animations = &Config::get().animations;
+        let enabled = animations.duration > 0;
+
+        let current = match &self.position {
+            Some(p) => p.clone(),
+            None => {
+                self.target_position = pos.clone();
+                let spawn = &animations.spawn;
+                if enabled && spawn.is_enabled() {
+                    let from = slide_offset(&pos, spawn.slide.as_ref(), self.scale);
+                    let from_opacity = if spawn.fade { 0.0 } else { 1.0 };
+                    self.animation = Some(Animation {
+                        from: from.clone(),
+                        to: pos,
+                        from_opacity,
+                        to_opacity: 1.0,
+                        elapsed: 0.0,
+                    });
+                    self.opacity = from_opacity;
+                    self.dirty = true;
+                    self.apply_position(from);
+                } else {
+                    self.apply_position(pos);
+                }
+                return;
+            }
+        };
+
+        // Closing windows keep their place while they go.
+        if self.closing || pos == self.target_position {
+            return;
+        }
+
+        self.target_position = pos.clone();
+        if enabled && animations.restack {
+            // Carry on from wherever we are, even if we're part-way through spawning.
+            self.animation = Some(Animation {
+                from: current,
+                to: pos,
+                from_opacity: self.opacity,
+                to_opacity: 1.0,
+                elapsed: 0.0,
+            });
+        } else if let Some(animation) = &mut self.animation {
+            animation.to = pos;
+        } else {
+            self.apply_position(pos);
+        }
+    }
+
+    // Start animating out, if the config says to.
+    // The window should stay alive for as long as `is_closing` says so.
+    pub fn begin_close(&mut self) {
+        let animations = &Config::get().animations;
+        if self.closing || animations.duration == 0 || !animations.close.is_enabled() {
+            return;
+        }
+
+        // Never made it onto the screen, so there's nothing to see.
+        let from = match &self.position {
+            Some(p) => p.clone(),
+            None => return,
+        };
+
+        let close = &animations.close;
+        self.closing = true;
+        self.animation = Some(Animation {
+            to: slide_offset(&from, close.slide.as_ref(), self.scale),
+            from,
+            from_opacity: self.opacity,
+            to_opacity: if close.fade { 0.0 } else { self.opacity },
+            elapsed: 0.0,
+        });
+    }
+
+    pub fn is_closing(&self) -> bool {
+        self.closing && self.animation.is_some()
+    }
+
+    fn apply_position(&mut self, pos: Vec2) {
+        self.set_position(pos.x, pos.y);
+        self.position = Some(pos);
+    }
+
+    // Step the current animation along.  Returns true when it finishes.
+    fn update_animation(&mut self, delta_time: Duration) -> bool {
+        let animation = match &mut self.animation {
+            Some(a) => a,
+            None => return false,
+        };
+
+        let animations = &Config::get().animations;
+        animation.elapsed += delta_time.as_secs_f64() * 1000.0;
+        let t = (animation.elapsed / animations.duration.max(1) as f64).min(1.0);
+        let eased = animations.easing.apply(t);
+
+        let pos = Vec2::new(
+            maths_utility::lerp(animation.from.x, animation.to.x, eased),
+            maths_utility::lerp(animation.from.y, animation.to.y, eased),
+        );
+        let opacity = maths_utility::lerp(animation.from_opacity, animation.to_opacity, eased);
+
+        self.apply_position(pos);
+        if (opacity - self.opacity).abs() > f64::EPSILON {
+            self.opacity = opacity;
+            self.dirty = true;
+        }
+
+        if t >= 1.0 {
+            self.animation = None;
+            return true;
+        }
+
+        false
     }
 
     pub fn _set_visible(&self, visible: bool) {
-        self.winit.set_visible(visible);
+        if let WindowHandle::X11(winit) = &self.handle {
+            winit.set_visible(visible);
+        }
     }
 
+    // Takes a size in logical pixels, see `scale`.
     pub fn set_size(&mut self, width: f64, height: f64) {
-        let _ = self.winit.request_inner_size(PhysicalSize { width, height });
-        unsafe {
-            cairo_sys::cairo_xlib_surface_set_size(self.surface.to_raw_none(), width as i32, height as i32);
+        match &mut self.handle {
+            WindowHandle::X11(winit) => {
+                let (width, height) = ((width * self.scale).ceil(), (height * self.scale).ceil());
+                let _ = winit.request_inner_size(PhysicalSize { width, height });
+                unsafe {
+                    cairo_sys::cairo_xlib_surface_set_size(
+                        self.surface.to_raw_none(),
+                        width as i32,
+                        height as i32,
+                    );
+                }
+                self.cached_inner_rect = Some(Rect::new(0.0, 0.0, width, height));
+            }
+            WindowHandle::Wayland(layer) => {
+                // Image surfaces can't be resized, so we start drawing to a new one.
+                let image = layer.resize(width, height);
+                self.context = Context::new(&image).expect("Failed to create cairo context.");
+                self.surface = (*image).clone();
+                self.text.update_context(&self.context);
+                // What the compositor will show, which is what we stack windows by.
+                let (width, height) = layer.logical_size();
+                self.cached_inner_rect = Some(Rect::new(0.0, 0.0, width * self.scale, height * self.scale));
+            }
         }
     }
 
     // Positioned rect on the desktop.
     pub fn _get_rect(&self) -> Rect {
-        let size = self.winit.inner_size();
-        let pos = self.winit.outer_position().expect("Window no longer exists.");
+        match &self.handle {
+            WindowHandle::X11(winit) => {
+                let size = winit.inner_size();
+                let pos = winit.outer_position().expect("Window no longer exists.");
 
-        Rect::new(pos.x.into(), pos.y.into(), size.width.into(), size.height.into())
+                Rect::new(pos.x.into(), pos.y.into(), size.width.into(), size.height.into())
+            }
+            // Layer surfaces can't tell us where they are, so this is where we last put them.
+            WindowHandle::Wayland(_) => {
+                let pos = self.position.clone().unwrap_or_default();
+                let size = self.get_inner_rect();
+                Rect::new(pos.x, pos.y, size.width(), size.height())
+            }
+        }
     }
 
-    // Pure rectangle, ignoring the window's position.
+    // Pure rectangle, ignoring the window's position.  In physical pixels.
     pub fn get_inner_rect(&self) -> Rect {
         if let Some(rect) = &self.cached_inner_rect {
             //assert!((r.width() - rr.width()).abs() < 0.001);
             //assert!((r.height() - rr.height()).abs() < 0.001);
             rect.clone()
         } else {
-            let size = self.winit.inner_size();
-            let rect = Rect::new(0.0, 0.0, size.width.into(), size.height.into());
-            rect
+            match &self.handle {
+                WindowHandle::X11(winit) => {
+                    let size = winit.inner_size();
+                    let rect = Rect::new(0.0, 0.0, size.width.into(), size.height.into());
+                    rect
+                }
+                WindowHandle::Wayland(layer) => {
+                    let (width, height) = layer.logical_size();
+                    Rect::new(0.0, 0.0, width * self.scale, height * self.scale)
+                }
+            }
         }
     }
 
@@ -334,7 +515,13 @@ impl NotifyWindow {
             eprintln!("A draw was triggered for a window that wasn't dirty!");
         }
 
-        let mut inner_rect = self.get_inner_rect();
+        let physical_rect = self.get_inner_rect();
+        let mut inner_rect = Rect::new(
+            0.0,
+            0.0,
+            physical_rect.width() / self.scale,
+            physical_rect.height() / self.scale,
+        );
         // If the master offset is anything other than `(0.0, 0.0)` it means that one of the
         // blocks is going to expand the big rectangle leftwards and/or upwards, which would
         // cause blocks to be drawn off canvas.
@@ -344,10 +531,103 @@ impl NotifyWindow {
         let mut layout = self.layout_take();
         layout.draw_tree(self, &inner_rect, Rect::empty(), false); // The criteria is parent_is_root, not is_root.  Bad but yeah.
         self.layout = Some(layout);
+        self.update_shape();
+
+        // X11 windows are drawn to directly, but Wayland needs to be handed the finished image.
+        if let WindowHandle::Wayland(layer) = &mut self.handle {
+            self.surface.flush();
+            layer.present();
+        }
+    }
+
+    // Cut the window down to what was just drawn, if the layout asks for it.  Only bothers the X
+    // server when the shape has actually changed.
+    fn update_shape(&mut self) {
+        let layout = self.layout.as_ref().expect("Window has no layout.");
+        let params = layout.as_notification_block();
+
+        let winit = match &self.handle {
+            WindowHandle::X11(winit) => winit,
+            // Layer surfaces can only take input everywhere or nowhere.
+            WindowHandle::Wayland(layer) => {
+                let input = params.click_through.then(Vec::new);
+                if input != self.shape.input {
+                    layer.set_click_through(params.click_through);
+                    self.shape.input = input;
+                }
+                return;
+            }
+        };
+        let scale = self.scale;
+
+        let drawn = if params.shape_input || params.shape_window {
+            let physical_rect = self.get_inner_rect();
+            let outline = params.drawn_outline(physical_rect.width() / scale, physical_rect.height() / scale);
+            let rects = match outline {
+                Some((rect, corners)) => {
+                    let rect = Rect::new(
+                        rect.x() * scale,
+                        rect.y() * scale,
+                        rect.width() * scale,
+                        rect.height() * scale,
+                    );
+                    maths_utility::rounded_rectangle_spans(&rect, &corners.map(|r| r * scale))
+                }
+                // Nothing but the blocks themselves, rounded out to cover every pixel they touch.
+                None => layout
+                    .drawn_child_rects()
+                    .iter()
+                    .map(|r| {
+                        let (left, top) = ((r.left() * scale).floor(), (r.top() * scale).floor());
+                        let (right, bottom) = ((r.right() * scale).ceil(), (r.bottom() * scale).ceil());
+                        Rect::new(left, top, right - left, bottom - top)
+                    })
+                    .collect(),
+            };
+            Some(rects)
+        } else {
+            None
+        };
+
+        let shape = WindowShape {
+            input: if params.click_through {
+                Some(vec![])
+            } else if params.shape_input {
+                drawn.clone()
+            } else {
+                None
+            },
+            bounding: if params.shape_window { drawn } else { None },
+        };
+
+        for (kind, rects, old) in [
+            (ShapeKind::Input, &shape.input, &self.shape.input),
+            (ShapeKind::Bounding, &shape.bounding, &self.shape.bounding),
+        ] {
+            if rects != old {
+                if let Err(e) = maths_utility::set_window_shape(winit, kind, rects.as_deref()) {
+                    eprintln!("Couldn't shape window: {}", e);
+                }
+            }
+        }
+
+        self.shape = shape;
     }
 
     pub fn update(&mut self, delta_time: Duration) -> bool {
-        if self.update_mode.contains(UpdateModes::FUSE) {
+        if let Some(grace) = &mut self.hover_grace {
+            *grace -= delta_time.as_secs_f64() * 1000.0;
+            if *grace <= 0.0 {
+                self.resume_from_hover();
+            }
+        }
+
+        // A finished close animation means the manager can get rid of us now.
+        if self.update_animation(delta_time) && self.closing {
+            return true;
+        }
+
+        if self.update_mode.contains(UpdateModes::FUSE) && !self.marked_for_destroy {
             if let Timeout::Milliseconds(ref mut fuse) = self.fuse {
                 *fuse -= delta_time.as_millis() as i32;
                 if *fuse <= 0 {
@@ -375,18 +655,252 @@ impl NotifyWindow {
         dirty
     }
 
+    // Pause the fuse while the cursor is over the window (if `pause_on_hover` is set).
+    pub fn set_hovered(&mut self, hovered: bool) {
+        if hovered {
+            self.hover_grace = None;
+            if self.update_mode.contains(UpdateModes::FUSE) {
+                self.update_mode.remove(UpdateModes::FUSE);
+                self.hover_paused = true;
+            }
+        } else if self.hover_paused && self.hover_grace.is_none() {
+            match Config::get().hover_grace_period {
+                0 => self.resume_from_hover(),
+                ms => self.hover_grace = Some(ms as f64),
+            }
+        }
+    }
+
+    fn resume_from_hover(&mut self) {
+        self.hover_paused = false;
+        self.hover_grace = None;
+        self.update_mode.insert(UpdateModes::FUSE);
+    }
+
+    // Pausing a window that's only paused because it's hovered makes the pause stick once the
+    // cursor leaves, rather than unpausing it.
+    pub fn toggle_pause(&mut self) {
+        if self.hover_paused {
+            self.hover_paused = false;
+            self.hover_grace = None;
+        } else {
+            self.update_mode.toggle(UpdateModes::FUSE);
+        }
+    }
+
     pub fn process_mouse_click(&mut self) {
         let mut layout = self.layout_take();
-        self.dirty |= layout.check_and_send_click(&self.last_mouse_pos, self);
+        self.dirty |= layout.check_and_send_click(self);
         self.layout = Some(layout);
     }
 
     pub fn process_mouse_move(&mut self, position: PhysicalPosition<f64>) {
-        self.last_mouse_pos.x = position.x;
-        self.last_mouse_pos.y = position.y;
+        // Layouts are in logical pixels.
+        self.last_mouse_pos.x = position.x / self.scale;
+        self.last_mouse_pos.y = position.y / self.scale;
 
         let mut layout = self.layout_take();
-        self.dirty |= layout.check_and_send_hover(&self.last_mouse_pos, self);
+        self.dirty |= layout.check_and_send_hover(self);
         self.layout = Some(layout);
     }
 }
+
+// Create a winit window with a cairo surface drawing to it.  `width` and `height` are logical.
+fn create_x11_window(
+    el: &EventLoopWindowTarget<()>,
+    manager: &NotifyWindowManager,
+    width: f64,
+    height: f64,
+    scale: f64,
+) -> (Window, Surface) {
+    // @NOTE: this is pretty messed up... It's annoying that winit only exposes a handle to the
+    // xlib display through an existing window, which means we have to use a dummy (hidden)
+    // window to grab it.
+    // We need the display to do `XMatchVisualInfo`, which we can't set after we've created the
+    // window.
+    // We might consider moving away from winit and just using xlib directly.  The only part
+    // we're really using at the moment is the event loop.
+    let xlib_display = get_xlib_display(&manager.base_window).expect("Couldn't get xlib_display.");
+
+    let visual_info = unsafe {
+        let mut vinfo = std::mem::MaybeUninit::<x11::xlib::XVisualInfo>::uninit();
+
+        let status = (x11::xlib::XMatchVisualInfo)(
+            xlib_display as _,
+            x11::xlib::XDefaultScreen(xlib_display as _) as i32,
+            32,
+            x11::xlib::TrueColor,
+            vinfo.as_mut_ptr(),
+        );
+
+        if status == 0 {
+            panic!("Couldn't get valid XVisualInfo.");
+        }
+
+        vinfo.assume_init()
+    };
+
+    // override_redirect bypasses the WM entirely on native X11, keeping us above everything.
+    // On XWayland (WAYLAND_DISPLAY is set) the compositor still controls stacking, so
+    // override_redirect breaks clicks and z-order instead.
+    let on_xwayland = std::env::var("WAYLAND_DISPLAY").is_ok();
+
+    let mut builder = WindowBuilder::new()
+        .with_inner_size(PhysicalSize {
+            width: width * scale,
+            height: height * scale,
+        })
+        .with_x11_window_type(vec![XWindowType::Notification, XWindowType::Utility])
+        .with_title("wired")
+        .with_x11_visual(visual_info.visualid as u32)
+        .with_transparent(true)
+        .with_decorations(false)
+        .with_window_level(WindowLevel::AlwaysOnTop)
+        // Spawn far off-screen instead of hidden — you can't draw to a hidden window.
+        .with_position(PhysicalPosition {
+            x: 999_999.0,
+            y: 999_999.0,
+        });
+
+    if !on_xwayland {
+        builder = builder.with_override_redirect(true);
+    }
+
+    let winit = builder.build(el).expect("Couldn't build winit window.");
+
+    // If these fail, it probably means we aren't on linux.
+    // In that case, we should fail before now however (`.with_x11_window_type()`).
+    let xlib_window =
+        get_xlib_window(&winit).expect("Couldn't get xlib window, make sure you're running X11.");
+
+    let surface = unsafe {
+        /*
+        let visual = x11::xlib::XDefaultVisual(
+            xlib_display as _,
+            0,
+        );
+        */
+
+        let sfc_raw = cairo_sys::cairo_xlib_surface_create(
+            xlib_display as _,
+            xlib_window,
+            visual_info.visual,
+            (width * scale) as _,
+            (height * scale) as _,
+        );
+
+        Surface::from_raw_full(sfc_raw)
+    }
+    .expect("Failed to create cairo surface.");
+    // Everything drawn is scaled up to physical pixels from here on.
+    surface.set_device_scale(scale, scale);
+
+    (winit, surface)
+}
+
+impl RenderContext for NotifyWindow {
+    fn context(&self) -> &Context {
+        &self.context
+    }
+
+    fn text(&self) -> &TextRenderer {
+        &self.text
+    }
+
+    fn notification(&self) -> &Notification {
+        &self.notification
+    }
+
+    fn scale(&self) -> f64 {
+        self.scale
+    }
+
+    fn opacity(&self) -> f64 {
+        self.opacity
+    }
+
+    fn update_mode(&self) -> UpdateModes {
+        self.update_mode
+    }
+
+    fn fuse(&self) -> &Timeout {
+        &self.fuse
+    }
+
+    fn fuse_total(&self) -> &Timeout {
+        &self.fuse_total
+    }
+
+    fn creation_timestamp(&self) -> DateTime<Local> {
+        self.creation_timestamp
+    }
+
+    fn mouse_pos(&self) -> &Vec2 {
+        &self.last_mouse_pos
+    }
+}
+
+// Work out how long a notification should last, from what the sender asked for and the timeout
+// settings of the config and the layout.
+pub fn resolve_fuse(notification: &Notification, layout: &LayoutBlock) -> Timeout {
+    let cfg = Config::get();
+    let layout_policy = &layout.as_notification_block().timeouts;
+    let setting = |get: fn(&TimeoutPolicy) -> Option<i32>| get(layout_policy).or_else(|| get(&cfg.timeouts));
+
+    match notification.timeout {
+        Timeout::Milliseconds(mut ms) => {
+            if let Some(min) = setting(|p| p.min) {
+                ms = ms.max(min);
+            }
+            if let Some(max) = setting(|p| p.max) {
+                ms = ms.min(max);
+            }
+            Timeout::Milliseconds(ms)
+        }
+        Timeout::NeverExpire => Timeout::NeverExpire,
+        Timeout::Default => {
+            let urgency_timeout = match notification.urgency {
+                Urgency::Low => setting(|p| p.low),
+                Urgency::Normal => setting(|p| p.normal),
+                Urgency::Critical => setting(|p| p.critical),
+            };
+
+            let base = match urgency_timeout {
+                Some(ms) if ms <= 0 => return Timeout::NeverExpire,
+                Some(ms) => ms,
+                None => cfg.timeout,
+            };
+
+            let reading_time = layout_policy
+                .reading_time
+                .as_ref()
+                .or(cfg.timeouts.reading_time.as_ref());
+            match reading_time {
+                Some(rt) => {
+                    // Only what's actually shown counts, not markup.
+                    let words = text::plain_text(&notification.summary).split_whitespace().count()
+                        + text::plain_text(&notification.body).split_whitespace().count();
+                    let read_ms = words as f64 / rt.words_per_minute.max(1.0) * 60_000.0;
+
+                    let mut ms = rt.base.unwrap_or(base) + read_ms as i32;
+                    if let Some(min) = rt.min {
+                        ms = ms.max(min);
+                    }
+                    if let Some(max) = rt.max {
+                        ms = ms.min(max);
+                    }
+                    Timeout::Milliseconds(ms)
+                }
+                None => Timeout::Milliseconds(base),
+            }
+        }
+    }
+}
+
+// Where a window slides in from or out to.  `slide` is in logical pixels, like layouts.
+fn slide_offset(pos: &Vec2, slide: Option<&Vec2>, scale: f64) -> Vec2 {
+    match slide {
+        Some(s) => Vec2::new(pos.x + s.x * scale, pos.y + s.y * scale),
+        None => pos.clone(),
+    }
+}
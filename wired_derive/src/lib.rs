@@ -58,42 +58,42 @@ fn impl_drawable_macro(ast: &syn::DeriveInput) -> TokenStream {
 
     let gen = quote! {
         impl DrawableLayoutElement for #name {
-            fn draw(&self, hook: &Hook, offset: &Vec2, parent_rect: &Rect, window: &NotifyWindow) -> Result<Rect, cairo::Error> {
-                window.context.save()?;
+            fn draw(&self, hook: &Hook, offset: &Vec2, parent_rect: &Rect, window: &dyn RenderContext) -> Result<Rect, cairo::Error> {
+                window.context().save()?;
                 // Default operator is Over, for ease of use.
-                window.context.set_operator(cairo::Operator::Over);
+                window.context().set_operator(cairo::Operator::Over);
                 let rect = match self {
                     #(#traverse_draw),*
                 };
-                window.context.restore()?;
+                window.context().restore()?;
 
                 rect
             }
 
-            fn predict_rect_and_init(&mut self, hook: &Hook, offset: &Vec2, parent_rect: &Rect, window: &NotifyWindow) -> Rect {
-                window.context.save().expect("Invalid cairo surface state.");
-                window.context.set_operator(cairo::Operator::Over);
+            fn predict_rect_and_init(&mut self, hook: &Hook, offset: &Vec2, parent_rect: &Rect, window: &dyn RenderContext) -> Rect {
+                window.context().save().expect("Invalid cairo surface state.");
+                window.context().set_operator(cairo::Operator::Over);
                 let rect = match self {
                     #(#traverse_predict),*
                 };
-                window.context.restore().expect("Invalid cairo surface state.");;
+                window.context().restore().expect("Invalid cairo surface state.");;
 
                 rect
             }
 
-            fn update(&mut self, delta_time: Duration, window: &NotifyWindow) -> bool {
+            fn update(&mut self, delta_time: Duration, window: &dyn RenderContext) -> bool {
                 match self {
                     #(#traverse_update),*
                 }
             }
 
-            fn clicked(&mut self, window: &NotifyWindow) -> bool {
+            fn clicked(&mut self, window: &dyn RenderContext) -> bool {
                 match self {
                     #(#traverse_clicked),*
                 }
             }
 
-            fn hovered(&mut self, entered: bool, window: &NotifyWindow) -> bool {
+            fn hovered(&mut self, entered: bool, window: &dyn RenderContext) -> bool {
                 match self {
                     #(#traverse_hovered),*
                 }